@@ -1,4 +1,8 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
 use std::fmt;
+use std::io::{self, BufRead};
+use std::num::IntErrorKind;
 
 pub fn exec(input: &str) {
     let res1 = testable_exec_part1(input);
@@ -47,13 +51,10 @@ pub fn exec(input: &str) {
 ///
 /// Find the Elf carrying the most Calories. How many total Calories is that Elf carrying?
 fn testable_exec_part1(input: &str) -> usize {
-    let elves = pack_elves(input);
-    let max_pack = elves
+    top_calories(input.as_bytes(), 1)
+        .unwrap_or_else(|e| panic!("{e}"))
         .iter()
-        .map(|elf| elf.food_pack.iter().sum::<usize>())
-        .max()
-        .unwrap();
-    max_pack
+        .sum()
 }
 
 /// --- Part Two ---
@@ -68,14 +69,28 @@ fn testable_exec_part1(input: &str) -> usize {
 ///
 /// Find the top three Elves carrying the most Calories. How many Calories are those Elves carrying in total?
 fn testable_exec_part2(input: &str) -> usize {
-    let elves = pack_elves(input);
-    let mut all_packs: Vec<usize> = elves
+    top_calories(input.as_bytes(), 3)
+        .unwrap_or_else(|e| panic!("{e}"))
         .iter()
-        .map(|elf| elf.food_pack.iter().sum::<usize>())
-        .collect();
-    all_packs.sort();
+        .sum()
+}
+
+/// Prints the calories carried by the top `k` elves of an inventory read
+/// from `reader`.
+pub fn exec_top<R: BufRead>(reader: R, k: usize) -> Result<(), InventoryError> {
+    let top = top_calories(reader, k)?;
+    println!(
+        "Day 1\n\ttop {k}: {top:?}\n\ttotal: {}",
+        top.iter().sum::<usize>()
+    );
+    Ok(())
+}
 
-    all_packs.iter().rev().take(3).sum::<usize>()
+/// Prints an overview of every elf in the inventory, useful for checking
+/// inputs that give surprising answers.
+pub fn exec_report(input: &str) {
+    let elves = pack_elves(input);
+    print!("{}", InventoryReport::new(&elves));
 }
 
 #[derive(Debug)]
@@ -89,26 +104,267 @@ impl fmt::Display for Elf {
     }
 }
 
+impl Elf {
+    fn calories(&self) -> usize {
+        self.food_pack.iter().sum()
+    }
+}
+
+#[derive(Debug)]
+pub enum InventoryError {
+    Io(io::Error),
+    InvalidCalories {
+        elf: usize,
+        line: usize,
+        item: String,
+    },
+    Overflow {
+        elf: usize,
+        line: usize,
+    },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InventoryError::Io(e) => write!(f, "could not read inventory: {e}"),
+            InventoryError::InvalidCalories { elf, line, item } => {
+                write!(f, "elf {elf}, line {line}: {item:?} is not a calorie count")
+            }
+            InventoryError::Overflow { elf, line } => {
+                write!(f, "elf {elf}, line {line}: calories overflow")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for InventoryError {
+    fn from(e: io::Error) -> Self {
+        InventoryError::Io(e)
+    }
+}
+
+/// Reads one elf at a time out of an inventory, only the elf currently being
+/// read is kept in memory.
+///
+/// Lines may end in `\r\n` and carry trailing whitespace, and any number of
+/// blank lines can separate two elves.
+struct Inventory<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    elf_number: usize,
+}
+
+impl<R: BufRead> Inventory<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            elf_number: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Inventory<R> {
+    type Item = Result<Elf, InventoryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut food_pack = vec![];
+        let mut total: usize = 0;
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) if food_pack.is_empty() => return None,
+                Ok(0) => break,
+                Ok(_) => self.line_number += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            let item = self.line.trim();
+            if item.is_empty() {
+                if food_pack.is_empty() {
+                    // Still between two elves
+                    continue;
+                }
+                break;
+            }
+            if food_pack.is_empty() {
+                self.elf_number += 1;
+            }
+            let calories = match item.parse::<usize>() {
+                Ok(calories) => calories,
+                Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+                    return Some(Err(InventoryError::Overflow {
+                        elf: self.elf_number,
+                        line: self.line_number,
+                    }))
+                }
+                Err(_) => {
+                    return Some(Err(InventoryError::InvalidCalories {
+                        elf: self.elf_number,
+                        line: self.line_number,
+                        item: item.to_string(),
+                    }))
+                }
+            };
+            total = match total.checked_add(calories) {
+                Some(total) => total,
+                None => {
+                    return Some(Err(InventoryError::Overflow {
+                        elf: self.elf_number,
+                        line: self.line_number,
+                    }))
+                }
+            };
+            food_pack.push(calories);
+        }
+        Some(Ok(Elf { food_pack }))
+    }
+}
+
 fn pack_elves(input: &str) -> Vec<Elf> {
-    let mut elves: Vec<Elf> = Vec::new();
-    let split_input: Vec<&str> = input.split("\n\n").collect();
-
-    for input_elf in split_input {
-        let lines = input_elf.lines();
-        let elf_pack: Vec<usize> = lines
-            .take_while(|e| *e != "")
-            .map(|e| e.parse::<usize>().unwrap())
+    Inventory::new(input.as_bytes())
+        .collect::<Result<Vec<Elf>, InventoryError>>()
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+const PERCENTILES: [usize; 5] = [25, 50, 75, 90, 99];
+
+#[derive(Debug, PartialEq)]
+struct RankedElf {
+    /// Position of the elf in the inventory, starting at 1
+    elf: usize,
+    items: usize,
+    calories: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct InventoryReport {
+    /// Every elf, most calories first
+    ranking: Vec<RankedElf>,
+    total: usize,
+    mean: f64,
+    median: f64,
+    /// Nearest rank percentiles of the calorie totals
+    percentiles: Vec<(usize, usize)>,
+    /// How many elves carry a given number of items
+    item_counts: BTreeMap<usize, usize>,
+}
+
+impl InventoryReport {
+    fn new(elves: &[Elf]) -> Self {
+        let mut ranking: Vec<RankedElf> = elves
+            .iter()
+            .enumerate()
+            .map(|(i, elf)| RankedElf {
+                elf: i + 1,
+                items: elf.food_pack.len(),
+                calories: elf.calories(),
+            })
             .collect();
-        elves.push(Elf {
-            food_pack: elf_pack.clone(),
-        });
+        ranking.sort_by_key(|r| Reverse(r.calories));
+
+        let mut totals: Vec<usize> = ranking.iter().map(|r| r.calories).collect();
+        totals.sort();
+        let n = totals.len();
+        let total = totals.iter().sum::<usize>();
+        let (mean, median) = match n {
+            0 => (0.0, 0.0),
+            _ if n % 2 == 1 => (total as f64 / n as f64, totals[n / 2] as f64),
+            _ => (
+                total as f64 / n as f64,
+                (totals[n / 2 - 1] + totals[n / 2]) as f64 / 2.0,
+            ),
+        };
+        let percentiles = match n {
+            0 => vec![],
+            _ => PERCENTILES
+                .iter()
+                .map(|&p| {
+                    let rank = ((p * n).div_ceil(100)).max(1);
+                    (p, totals[rank - 1])
+                })
+                .collect(),
+        };
+
+        let mut item_counts = BTreeMap::new();
+        for elf in elves {
+            *item_counts.entry(elf.food_pack.len()).or_insert(0) += 1;
+        }
+
+        Self {
+            ranking,
+            total,
+            mean,
+            median,
+            percentiles,
+            item_counts,
+        }
+    }
+}
+
+impl fmt::Display for InventoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "\telves: {}, total calories: {}",
+            self.ranking.len(),
+            self.total
+        )?;
+        writeln!(f, "\tmean: {:.1}, median: {:.1}", self.mean, self.median)?;
+        let percentiles: Vec<String> = self
+            .percentiles
+            .iter()
+            .map(|(p, calories)| format!("p{p} {calories}"))
+            .collect();
+        writeln!(f, "\tpercentiles: {}", percentiles.join(", "))?;
+        writeln!(f, "\titems per elf:")?;
+        for (items, elves) in &self.item_counts {
+            writeln!(f, "\t\t{items:>3} items: {elves} elves")?;
+        }
+        writeln!(f, "\tranking:")?;
+        writeln!(
+            f,
+            "\t\t{:>5} {:>5} {:>5} {:>9}",
+            "rank", "elf", "items", "calories"
+        )?;
+        for (rank, r) in self.ranking.iter().enumerate() {
+            writeln!(
+                f,
+                "\t\t{:>5} {:>5} {:>5} {:>9}",
+                rank + 1,
+                r.elf,
+                r.items,
+                r.calories
+            )?;
+        }
+        Ok(())
     }
-    elves
+}
+
+/// Returns the calories carried by the `k` best stocked elves, most first.
+/// Reads the inventory in a single pass keeping only the best `k` totals in
+/// a min-heap, so inventories larger than memory are fine.
+fn top_calories<R: BufRead>(reader: R, k: usize) -> Result<Vec<usize>, InventoryError> {
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for elf in Inventory::new(reader) {
+        heap.push(Reverse(elf?.calories()));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(calories)| calories)
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::Lcg;
 
     #[test]
     fn part_1_works() {
@@ -121,4 +377,118 @@ mod tests {
         let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
         assert_eq!(testable_exec_part2(input), 45000)
     }
+
+    #[test]
+    fn top_calories_works() {
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+        assert_eq!(
+            top_calories(input.as_bytes(), 3).unwrap(),
+            vec![24000, 11000, 10000]
+        );
+        assert_eq!(top_calories(input.as_bytes(), 0).unwrap(), vec![]);
+        assert_eq!(
+            top_calories(input.as_bytes(), 10).unwrap(),
+            vec![24000, 11000, 10000, 6000, 4000]
+        );
+    }
+
+    /// Generates an inventory on the fly without ever holding all of it.
+    struct GeneratedInventory {
+        elves_left: usize,
+        rng: Lcg,
+        buf: Vec<u8>,
+    }
+
+    impl io::Read for GeneratedInventory {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            if self.buf.is_empty() && self.elves_left > 0 {
+                self.elves_left -= 1;
+                for _ in 0..self.rng.below(16) + 1 {
+                    let calories = self.rng.below(10000);
+                    self.buf.extend(format!("{calories}\n").bytes());
+                }
+                self.buf.push(b'\n');
+            }
+            let n = out.len().min(self.buf.len());
+            out[..n].copy_from_slice(&self.buf[..n]);
+            self.buf.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn top_calories_matches_sorting_on_generated_inventory() {
+        let generate = || GeneratedInventory {
+            elves_left: 50000,
+            rng: Lcg::new(1),
+            buf: vec![],
+        };
+        let mut all: Vec<usize> = Inventory::new(io::BufReader::new(generate()))
+            .map(|elf| elf.unwrap().calories())
+            .collect();
+        all.sort();
+        all.reverse();
+        all.truncate(5);
+        let top = top_calories(io::BufReader::new(generate()), 5).unwrap();
+        assert_eq!(top, all);
+    }
+
+    #[test]
+    fn inventory_report_works() {
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+        let report = InventoryReport::new(&pack_elves(input));
+        let ranked: Vec<usize> = report.ranking.iter().map(|r| r.elf).collect();
+        assert_eq!(ranked, vec![4, 3, 5, 1, 2]);
+        assert_eq!(report.total, 55000);
+        assert_eq!(report.mean, 11000.0);
+        assert_eq!(report.median, 10000.0);
+        assert_eq!(
+            report.percentiles,
+            vec![
+                (25, 6000),
+                (50, 10000),
+                (75, 11000),
+                (90, 24000),
+                (99, 24000)
+            ]
+        );
+        assert_eq!(report.item_counts, BTreeMap::from([(1, 2), (2, 1), (3, 2)]));
+    }
+
+    #[test]
+    fn inventory_report_even_and_empty() {
+        let report = InventoryReport::new(&pack_elves("1\n\n3\n"));
+        assert_eq!(report.median, 2.0);
+        let report = InventoryReport::new(&pack_elves(""));
+        assert!(report.ranking.is_empty());
+        assert!(report.percentiles.is_empty());
+    }
+
+    #[test]
+    fn inventory_tolerates_crlf_and_blank_runs() {
+        let input = "\r\n1000\r\n2000  \r\n\r\n\r\n\r\n4000\r\n\n\n";
+        let elves: Vec<Vec<usize>> = pack_elves(input)
+            .into_iter()
+            .map(|elf| elf.food_pack)
+            .collect();
+        assert_eq!(elves, vec![vec![1000, 2000], vec![4000]]);
+    }
+
+    #[test]
+    fn inventory_reports_bad_lines() {
+        let input = "1000\n\n\n2000\n20x0\n";
+        let err = top_calories(input.as_bytes(), 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "elf 2, line 5: \"20x0\" is not a calorie count"
+        );
+
+        let input = "1\n\n99999999999999999999999\n";
+        let err = top_calories(input.as_bytes(), 1).unwrap_err();
+        assert_eq!(err.to_string(), "elf 2, line 3: calories overflow");
+
+        let input = format!("1\n{}\n", usize::MAX);
+        let err = top_calories(input.as_bytes(), 1).unwrap_err();
+        assert_eq!(err.to_string(), "elf 1, line 2: calories overflow");
+    }
 }
@@ -1,3 +1,7 @@
+use std::cmp::{Ordering, Reverse};
+use std::fmt;
+use std::str::FromStr;
+
 /// --- Day 2: Rock Paper Scissors ---
 ///
 /// The Elves begin to set up camp on the beach. To decide whose tent gets to be closest to the snack storage,
@@ -38,49 +42,191 @@
 ///
 /// What would your total score be if everything goes exactly according to your strategy guide?
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum RPS {
     Rock,
     Paper,
     Scissors,
 }
 
+impl FromStr for RPS {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "A" => Ok(RPS::Rock),
+            "B" => Ok(RPS::Paper),
+            "C" => Ok(RPS::Scissors),
+            _ => Err(format!("expected A, B or C, found {s:?}")),
+        }
+    }
+}
+
 impl RPS {
-    fn from_str(input: &str) -> RPS {
-        match input {
-            "A" | "X" => return RPS::Rock,
-            "B" | "Y" => return RPS::Paper,
-            "C" | "Z" => return RPS::Scissors,
-            &_ => todo!(),
+    /// Position of the shape in the n = 3 `CyclicGame`.
+    fn shape(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+/// The second column of the strategy guide. What it means depends on who
+/// you ask, so it is kept as written and interpreted by each part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    X,
+    Y,
+    Z,
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "X" => Ok(Column::X),
+            "Y" => Ok(Column::Y),
+            "Z" => Ok(Column::Z),
+            _ => Err(format!("expected X, Y or Z, found {s:?}")),
         }
     }
+}
 
-    fn points(self) -> u32 {
+impl Column {
+    /// Part 1, the column is the shape to play.
+    fn as_shape(self) -> RPS {
         match self {
-            RPS::Rock => 1,
-            RPS::Paper => 2,
-            RPS::Scissors => 3,
+            Column::X => RPS::Rock,
+            Column::Y => RPS::Paper,
+            Column::Z => RPS::Scissors,
         }
     }
 
-    fn compare(self, second_hand: &RPS) -> i8 {
+    /// Part 2, the column is how the round needs to end.
+    fn as_outcome(self) -> Outcome {
         match self {
-            RPS::Rock => match second_hand {
-                RPS::Rock => 0,
-                RPS::Paper => -1,
-                RPS::Scissors => 1,
-            },
-            RPS::Paper => match second_hand {
-                RPS::Rock => 1,
-                RPS::Paper => 0,
-                RPS::Scissors => -1,
-            },
-            RPS::Scissors => match second_hand {
-                RPS::Rock => -1,
-                RPS::Paper => 1,
-                RPS::Scissors => 0,
-            },
+            Column::X => Outcome::Lose,
+            Column::Y => Outcome::Draw,
+            Column::Z => Outcome::Win,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Round {
+    opponent: RPS,
+    column: Column,
+}
+
+#[derive(Debug, PartialEq)]
+struct StrategyGuide {
+    rounds: Vec<Round>,
+}
+
+#[derive(Debug, PartialEq)]
+struct ParseStrategyGuideError {
+    line: usize,
+    reason: String,
+}
+
+impl fmt::Display for ParseStrategyGuideError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl FromStr for StrategyGuide {
+    type Err = ParseStrategyGuideError;
+
+    /// Reads one round per line, blank lines are skipped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rounds = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let error = |reason| ParseStrategyGuideError {
+                line: i + 1,
+                reason,
+            };
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            match columns[..] {
+                [] => continue,
+                [opponent, column] => rounds.push(Round {
+                    opponent: opponent.parse().map_err(error)?,
+                    column: column.parse().map_err(error)?,
+                }),
+                _ => {
+                    return Err(error(format!(
+                        "expected two columns, found {}",
+                        columns.len()
+                    )))
+                }
+            }
         }
+        Ok(StrategyGuide { rounds })
+    }
+}
+
+/// Rock Paper Scissors generalised to any odd number of shapes. The shapes
+/// sit on a cycle and each one beats the shapes an odd number of steps behind
+/// it, so every shape beats exactly half of the others. Rock Paper Scissors is
+/// the n = 3 game, Rock Paper Scissors Spock Lizard the n = 5 one.
+#[derive(Debug, Clone)]
+struct CyclicGame {
+    /// Score for playing each shape
+    shape_scores: Vec<u32>,
+    /// Score for losing, drawing and winning a round, in that order
+    outcome_scores: [u32; 3],
+}
+
+impl CyclicGame {
+    fn new(shape_scores: Vec<u32>, outcome_scores: [u32; 3]) -> Self {
+        assert!(
+            shape_scores.len() % 2 == 1,
+            "Cyclic games need an odd number of shapes"
+        );
+        Self {
+            shape_scores,
+            outcome_scores,
+        }
+    }
+
+    fn rock_paper_scissors() -> Self {
+        Self::new(vec![1, 2, 3], [0, 3, 6])
+    }
+
+    fn shapes(&self) -> usize {
+        self.shape_scores.len()
+    }
+
+    /// How the round ends for us when we play `mine` against `theirs`.
+    fn outcome(&self, mine: usize, theirs: usize) -> Outcome {
+        let n = self.shapes();
+        match (mine + n - theirs) % n {
+            0 => Outcome::Draw,
+            steps if steps % 2 == 1 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
+    }
+
+    /// The shape to play against `theirs` for the round to end in `outcome`.
+    fn response(&self, theirs: usize, outcome: Outcome) -> usize {
+        let n = self.shapes();
+        match outcome {
+            Outcome::Lose => (theirs + n - 1) % n,
+            Outcome::Draw => theirs,
+            Outcome::Win => (theirs + 1) % n,
+        }
+    }
+
+    /// Score for a round where we play `mine` against `theirs`.
+    fn score(&self, mine: usize, theirs: usize) -> u32 {
+        let outcome = self.outcome(mine, theirs);
+        self.shape_scores[mine] + self.outcome_scores[outcome as usize]
     }
 }
 
@@ -105,72 +251,344 @@ impl RPS {
 /// what would your total score be if everything goes exactly according to your strategy guide?
 
 pub fn exec(input: &str) {
-    // Part 1
-    let playbook: _ = input
-        .lines()
-        .map(|line| {
-            let x: Vec<RPS> = line
-                .split_whitespace()
-                .map(|hand| RPS::from_str(hand))
-                .collect();
-            x
-        })
-        .collect::<Vec<Vec<RPS>>>();
+    let res1 = testable_exec_part1(input);
+    let res2 = testable_exec_part2(input);
+
+    println!("Day 2\n\tpart 1: {res1}\n\tpart 2: {res2}");
+}
+
+fn parse_guide(input: &str) -> StrategyGuide {
+    match StrategyGuide::from_str(input) {
+        Ok(guide) => guide,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+fn testable_exec_part1(input: &str) -> u32 {
+    let game = CyclicGame::rock_paper_scissors();
+    parse_guide(input)
+        .rounds
+        .iter()
+        .map(|round| game.score(round.column.as_shape().shape(), round.opponent.shape()))
+        .sum()
+}
 
-    let result: u32 = playbook
+fn testable_exec_part2(input: &str) -> u32 {
+    let game = CyclicGame::rock_paper_scissors();
+    parse_guide(input)
+        .rounds
         .iter()
-        .map(|play| {
-            let h1 = play.get(0).unwrap();
-            let h2 = play.get(1).unwrap();
-            match h2.compare(h1) {
-                -1 => 0 + h2.points(),
-                0 => 3 + h2.points(),
-                1 => 6 + h2.points(),
-                _ => todo!(),
+        .map(|round| follow_play(&game, &round.opponent, round.column.as_outcome()))
+        .sum()
+}
+
+fn follow_play(game: &CyclicGame, opposing_hand: &RPS, condition: Outcome) -> u32 {
+    let hand = game.response(opposing_hand.shape(), condition);
+    game.score(hand, opposing_hand.shape())
+}
+
+/// A possible meaning of the second column, either the shape to play or how
+/// the round should end for each of X, Y and Z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Decoding {
+    Shapes([RPS; 3]),
+    Outcomes([Outcome; 3]),
+}
+
+impl fmt::Display for Decoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Decoding::Shapes([x, y, z]) => write!(f, "X={x:?} Y={y:?} Z={z:?}"),
+            Decoding::Outcomes([x, y, z]) => write!(f, "X={x:?} Y={y:?} Z={z:?}"),
+        }
+    }
+}
+
+/// Every way of assigning three things to X, Y and Z.
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+fn all_decodings() -> Vec<Decoding> {
+    let shapes = [RPS::Rock, RPS::Paper, RPS::Scissors];
+    let outcomes = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+    let as_shapes = PERMUTATIONS.map(|p| Decoding::Shapes(p.map(|i| shapes[i])));
+    let as_outcomes = PERMUTATIONS.map(|p| Decoding::Outcomes(p.map(|i| outcomes[i])));
+    as_shapes.into_iter().chain(as_outcomes).collect()
+}
+
+fn score_decoding(guide: &StrategyGuide, decoding: &Decoding) -> u32 {
+    let game = CyclicGame::rock_paper_scissors();
+    guide
+        .rounds
+        .iter()
+        .map(|round| match decoding {
+            Decoding::Shapes(shapes) => {
+                let hand = shapes[round.column as usize];
+                game.score(hand.shape(), round.opponent.shape())
             }
-        })
-        .sum();
-    println!("Day 2");
-    println!("\tpart 1:{result}");
-
-    // Part 2
-    let actual_score: u32 = input
-        .lines()
-        .map(|line| {
-            let s: Vec<&str> = line.split_whitespace().collect();
-            let opponent_hand = RPS::from_str(s.get(0).unwrap());
-            let play_result = s.get(1).unwrap();
-            let score;
-            match play_result {
-                &"X" => score = follow_play(&opponent_hand, "lose"),
-                &"Y" => score = follow_play(&opponent_hand, "draw"),
-                &"Z" => score = follow_play(&opponent_hand, "win"),
-                &_ => score = 0,
+            Decoding::Outcomes(outcomes) => {
+                follow_play(&game, &round.opponent, outcomes[round.column as usize])
             }
-            score
         })
-        .sum();
-    println!("\tpart 2: {actual_score}");
-}
-
-fn follow_play(opposing_hand: &RPS, condition: &str) -> u32 {
-    match condition {
-        "lose" => match opposing_hand {
-            RPS::Rock => RPS::Scissors.points() + 0,
-            RPS::Paper => RPS::Rock.points() + 0,
-            RPS::Scissors => RPS::Paper.points() + 0,
-        },
-        "draw" => match opposing_hand {
-            RPS::Rock => RPS::Rock.points() + 3,
-            RPS::Paper => RPS::Paper.points() + 3,
-            RPS::Scissors => RPS::Scissors.points() + 3,
-        },
-        "win" => match opposing_hand {
-            RPS::Rock => RPS::Paper.points() + 6,
-            RPS::Paper => RPS::Scissors.points() + 6,
-            RPS::Scissors => RPS::Rock.points() + 6,
-        },
-        &_ => todo!(),
+        .sum()
+}
+
+/// Scores the guide under every possible decoding, best first.
+fn explore_decodings(guide: &StrategyGuide) -> Vec<(Decoding, u32)> {
+    let mut scores: Vec<(Decoding, u32)> = all_decodings()
+        .into_iter()
+        .map(|decoding| (decoding, score_decoding(guide, &decoding)))
+        .collect();
+    scores.sort_by_key(|&(_, score)| Reverse(score));
+    scores
+}
+
+/// Prints how the guide scores under every way the second column could be
+/// read, not just the two from the puzzle.
+pub fn exec_explore(input: &str) {
+    let scores = explore_decodings(&parse_guide(input));
+    println!("Day 2 decodings");
+    if let (Some((best, high)), Some((worst, low))) = (scores.first(), scores.last()) {
+        println!("\tbest: {high} ({best})\n\tworst: {low} ({worst})");
+    }
+    for (decoding, score) in &scores {
+        let kind = match decoding {
+            Decoding::Shapes(_) => "shapes",
+            Decoding::Outcomes(_) => "outcomes",
+        };
+        println!("\t\t{score:>6}  {kind:<8} {decoding}");
+    }
+}
+
+/// How a tournament player picks its shapes.
+#[derive(Debug, Clone, PartialEq)]
+enum Strategy {
+    /// Plays the shapes from a strategy guide in order, starting over when
+    /// it runs out
+    Guide(Vec<RPS>),
+    AlwaysRock,
+    /// Rock, Paper, Scissors, Rock, ...
+    Cycle,
+    /// Plays whatever the opponent played last round, Rock to start with
+    CopyLast,
+}
+
+impl Strategy {
+    /// Reads the guide the way part 1 does, the second column is our shape.
+    fn from_guide(guide: &StrategyGuide) -> Self {
+        Strategy::Guide(guide.rounds.iter().map(|r| r.column.as_shape()).collect())
+    }
+
+    /// Shape to play in `round`, counting from 0.
+    fn play(&self, round: usize, opponent_last: Option<RPS>) -> RPS {
+        let shapes = [RPS::Rock, RPS::Paper, RPS::Scissors];
+        match self {
+            Strategy::Guide(hands) if hands.is_empty() => RPS::Rock,
+            Strategy::Guide(hands) => hands[round % hands.len()],
+            Strategy::AlwaysRock => RPS::Rock,
+            Strategy::Cycle => shapes[round % shapes.len()],
+            Strategy::CopyLast => opponent_last.unwrap_or(RPS::Rock),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Player {
+    name: String,
+    strategy: Strategy,
+}
+
+impl Player {
+    fn new(name: &str, strategy: Strategy) -> Self {
+        Self {
+            name: name.to_string(),
+            strategy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    RoundRobin,
+    Elimination,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round-robin" => Ok(Format::RoundRobin),
+            "elimination" => Ok(Format::Elimination),
+            _ => Err(format!("unknown tournament format: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Standing {
+    name: String,
+    /// Sum of every round score, shape plus outcome
+    score: u32,
+    wins: usize,
+    draws: usize,
+    losses: usize,
+    /// Last bracket stage reached, only used in elimination
+    stage: usize,
+}
+
+impl Standing {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            score: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            stage: 0,
+        }
+    }
+}
+
+/// Plays `rounds` rounds between two players and returns their total scores.
+fn play_match(game: &CyclicGame, a: &Player, b: &Player, rounds: usize) -> (u32, u32) {
+    let (mut score_a, mut score_b) = (0, 0);
+    let (mut last_a, mut last_b) = (None, None);
+    for round in 0..rounds {
+        let hand_a = a.strategy.play(round, last_b);
+        let hand_b = b.strategy.play(round, last_a);
+        score_a += game.score(hand_a.shape(), hand_b.shape());
+        score_b += game.score(hand_b.shape(), hand_a.shape());
+        (last_a, last_b) = (Some(hand_a), Some(hand_b));
+    }
+    (score_a, score_b)
+}
+
+/// Plays a match and books the result, returns true if `a` won or drew.
+fn record_match(
+    game: &CyclicGame,
+    players: &[Player],
+    standings: &mut [Standing],
+    (a, b): (usize, usize),
+    rounds: usize,
+) -> bool {
+    let (score_a, score_b) = play_match(game, &players[a], &players[b], rounds);
+    standings[a].score += score_a;
+    standings[b].score += score_b;
+    match score_a.cmp(&score_b) {
+        Ordering::Greater => {
+            standings[a].wins += 1;
+            standings[b].losses += 1;
+        }
+        Ordering::Less => {
+            standings[a].losses += 1;
+            standings[b].wins += 1;
+        }
+        Ordering::Equal => {
+            standings[a].draws += 1;
+            standings[b].draws += 1;
+        }
+    }
+    score_a >= score_b
+}
+
+/// Every player meets every other player once. Ranked by match wins, then
+/// draws, then total score.
+fn round_robin(players: &[Player], rounds: usize) -> Vec<Standing> {
+    let game = CyclicGame::rock_paper_scissors();
+    let mut standings: Vec<Standing> = players.iter().map(|p| Standing::new(&p.name)).collect();
+    for a in 0..players.len() {
+        for b in (a + 1)..players.len() {
+            record_match(&game, players, &mut standings, (a, b), rounds);
+        }
+    }
+    standings.sort_by_key(|s| Reverse((s.wins, s.draws, s.score)));
+    standings
+}
+
+/// Single elimination bracket in seeding order. A drawn match goes to the
+/// higher seed and an odd player out gets a bye to the next stage. Ranked by
+/// stage reached, then total score.
+fn elimination(players: &[Player], rounds: usize) -> Vec<Standing> {
+    let game = CyclicGame::rock_paper_scissors();
+    let mut standings: Vec<Standing> = players.iter().map(|p| Standing::new(&p.name)).collect();
+    let mut alive: Vec<usize> = (0..players.len()).collect();
+    let mut stage = 0;
+    while alive.len() > 1 {
+        stage += 1;
+        let mut next = vec![];
+        for pair in alive.chunks(2) {
+            match *pair {
+                [a, b] => {
+                    standings[a].stage = stage;
+                    standings[b].stage = stage;
+                    let a_won = record_match(&game, players, &mut standings, (a, b), rounds);
+                    next.push(if a_won { a } else { b });
+                }
+                [bye] => {
+                    standings[bye].stage = stage;
+                    next.push(bye);
+                }
+                _ => unreachable!(),
+            }
+        }
+        alive = next;
+    }
+    for champion in alive {
+        standings[champion].stage = stage + 1;
+    }
+    standings.sort_by_key(|s| Reverse((s.stage, s.score)));
+    standings
+}
+
+/// The puzzle's guide, then each of `guides` as a named player, then the
+/// built-in strategies.
+fn tournament_players(guide: &StrategyGuide, guides: &[(String, String)]) -> Vec<Player> {
+    let mut players = vec![Player::new("guide", Strategy::from_guide(guide))];
+    for (name, input) in guides {
+        let guide = StrategyGuide::from_str(input).unwrap_or_else(|e| panic!("{name}: {e}"));
+        players.push(Player::new(name, Strategy::from_guide(&guide)));
+    }
+    players.extend([
+        Player::new("always-rock", Strategy::AlwaysRock),
+        Player::new("cycle", Strategy::Cycle),
+        Player::new("copy-last", Strategy::CopyLast),
+    ]);
+    players
+}
+
+/// Runs the strategy guide and any extra `guides`, given as name and
+/// contents, against the built-in strategies. Every match is as long as the
+/// puzzle's guide.
+pub fn exec_tournament(input: &str, guides: &[(String, String)], format: Format) {
+    let guide = parse_guide(input);
+    let rounds = guide.rounds.len();
+    let players = tournament_players(&guide, guides);
+    let standings = match format {
+        Format::RoundRobin => round_robin(&players, rounds),
+        Format::Elimination => elimination(&players, rounds),
+    };
+    println!("Day 2 tournament ({format:?}, {rounds} rounds per match)");
+    println!(
+        "\t{:>4} {:<12} {:>4} {:>5} {:>6} {:>8}",
+        "rank", "player", "won", "drawn", "lost", "score"
+    );
+    for (rank, s) in standings.iter().enumerate() {
+        println!(
+            "\t{:>4} {:<12} {:>4} {:>5} {:>6} {:>8}",
+            rank + 1,
+            s.name,
+            s.wins,
+            s.draws,
+            s.losses,
+            s.score
+        );
     }
 }
 
@@ -179,14 +597,212 @@ mod tests {
     use super::*;
     #[test]
     fn follow_play_works_with_example() {
-        assert_eq!(follow_play(&RPS::Rock, "draw"), 4);
-        assert_eq!(follow_play(&RPS::Rock, "lose"), 3);
-        assert_eq!(follow_play(&RPS::Rock, "win"), 8);
-        assert_eq!(follow_play(&RPS::Paper, "draw"), 5);
-        assert_eq!(follow_play(&RPS::Paper, "lose"), 1);
-        assert_eq!(follow_play(&RPS::Paper, "win"), 9);
-        assert_eq!(follow_play(&RPS::Scissors, "draw"), 6);
-        assert_eq!(follow_play(&RPS::Scissors, "lose"), 2);
-        assert_eq!(follow_play(&RPS::Scissors, "win"), 7);
+        let game = CyclicGame::rock_paper_scissors();
+        assert_eq!(follow_play(&game, &RPS::Rock, Outcome::Draw), 4);
+        assert_eq!(follow_play(&game, &RPS::Rock, Outcome::Lose), 3);
+        assert_eq!(follow_play(&game, &RPS::Rock, Outcome::Win), 8);
+        assert_eq!(follow_play(&game, &RPS::Paper, Outcome::Draw), 5);
+        assert_eq!(follow_play(&game, &RPS::Paper, Outcome::Lose), 1);
+        assert_eq!(follow_play(&game, &RPS::Paper, Outcome::Win), 9);
+        assert_eq!(follow_play(&game, &RPS::Scissors, Outcome::Draw), 6);
+        assert_eq!(follow_play(&game, &RPS::Scissors, Outcome::Lose), 2);
+        assert_eq!(follow_play(&game, &RPS::Scissors, Outcome::Win), 7);
+    }
+
+    #[test]
+    fn rock_paper_scissors_outcomes() {
+        let game = CyclicGame::rock_paper_scissors();
+        let (rock, paper, scissors) =
+            (RPS::Rock.shape(), RPS::Paper.shape(), RPS::Scissors.shape());
+        assert_eq!(game.outcome(paper, rock), Outcome::Win);
+        assert_eq!(game.outcome(rock, scissors), Outcome::Win);
+        assert_eq!(game.outcome(scissors, paper), Outcome::Win);
+        assert_eq!(game.outcome(rock, paper), Outcome::Lose);
+        assert_eq!(game.outcome(paper, paper), Outcome::Draw);
+        assert_eq!(game.score(paper, rock), 8);
+        assert_eq!(game.score(rock, paper), 1);
+        assert_eq!(game.score(scissors, scissors), 6);
+    }
+
+    #[test]
+    fn rock_paper_scissors_spock_lizard() {
+        // Rock, Paper, Scissors, Spock, Lizard
+        let game = CyclicGame::new(vec![1, 2, 3, 4, 5], [0, 10, 20]);
+        let (rock, paper, scissors, spock, lizard) = (0, 1, 2, 3, 4);
+        assert_eq!(game.outcome(spock, rock), Outcome::Win);
+        assert_eq!(game.outcome(spock, scissors), Outcome::Win);
+        assert_eq!(game.outcome(lizard, spock), Outcome::Win);
+        assert_eq!(game.outcome(lizard, paper), Outcome::Win);
+        assert_eq!(game.outcome(paper, spock), Outcome::Win);
+        assert_eq!(game.outcome(rock, lizard), Outcome::Win);
+        assert_eq!(game.outcome(scissors, lizard), Outcome::Win);
+        assert_eq!(game.score(spock, rock), 24);
+
+        for theirs in 0..game.shapes() {
+            let wins = (0..game.shapes())
+                .filter(|&mine| game.outcome(mine, theirs) == Outcome::Win)
+                .count();
+            assert_eq!(wins, 2);
+            for outcome in [Outcome::Lose, Outcome::Draw, Outcome::Win] {
+                let mine = game.response(theirs, outcome);
+                assert_eq!(game.outcome(mine, theirs), outcome);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn cyclic_game_needs_odd_shapes() {
+        CyclicGame::new(vec![1, 2, 3, 4], [0, 3, 6]);
+    }
+
+    #[test]
+    fn part_1_example_works() {
+        assert_eq!(testable_exec_part1("A Y\nB X\nC Z\n"), 15);
+    }
+
+    #[test]
+    fn part_2_example_works() {
+        assert_eq!(testable_exec_part2("A Y\nB X\nC Z\n"), 12);
+    }
+
+    #[test]
+    fn strategy_guide_parses() {
+        let guide = StrategyGuide::from_str("A Y\n\nC  Z\n").unwrap();
+        assert_eq!(
+            guide.rounds,
+            vec![
+                Round {
+                    opponent: RPS::Rock,
+                    column: Column::Y
+                },
+                Round {
+                    opponent: RPS::Scissors,
+                    column: Column::Z
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn strategy_guide_reports_bad_rows() {
+        let err = StrategyGuide::from_str("A Y\nB W\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected X, Y or Z, found \"W\"");
+        let err = StrategyGuide::from_str("A Y\nB X\nX A\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: expected A, B or C, found \"X\"");
+        let err = StrategyGuide::from_str("A Y Z\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: expected two columns, found 3");
+        let err = StrategyGuide::from_str("A Y\nB\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn explore_covers_both_parts() {
+        let guide = StrategyGuide::from_str("A Y\nB X\nC Z\n").unwrap();
+        let scores = explore_decodings(&guide);
+        assert_eq!(scores.len(), 12);
+        let score_of = |decoding| scores.iter().find(|(d, _)| *d == decoding).unwrap().1;
+        assert_eq!(
+            score_of(Decoding::Shapes([RPS::Rock, RPS::Paper, RPS::Scissors])),
+            15
+        );
+        assert_eq!(
+            score_of(Decoding::Outcomes([
+                Outcome::Lose,
+                Outcome::Draw,
+                Outcome::Win
+            ])),
+            12
+        );
+        // Winning all three rounds with the best shapes
+        assert_eq!(scores[0].1, 24);
+        assert!(scores.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn built_in_strategies_play_matches() {
+        let game = CyclicGame::rock_paper_scissors();
+        let rock = Player::new("rock", Strategy::AlwaysRock);
+        let cycle = Player::new("cycle", Strategy::Cycle);
+        let copy = Player::new("copy", Strategy::CopyLast);
+        // Rock against Rock, Paper and Scissors
+        assert_eq!(play_match(&game, &cycle, &rock, 3), (4 + 8 + 3, 4 + 1 + 7));
+        // Copying always rock is a draw every round
+        assert_eq!(play_match(&game, &copy, &rock, 5), (20, 20));
+        // Copying cycle plays one shape behind and always loses after round 1
+        assert_eq!(play_match(&game, &copy, &cycle, 3), (4 + 1 + 2, 4 + 8 + 9));
+    }
+
+    #[test]
+    fn guide_strategy_repeats() {
+        let guide = StrategyGuide::from_str("A Y\nB X\n").unwrap();
+        let strategy = Strategy::from_guide(&guide);
+        assert_eq!(strategy.play(0, None), RPS::Paper);
+        assert_eq!(strategy.play(1, None), RPS::Rock);
+        assert_eq!(strategy.play(2, None), RPS::Paper);
+    }
+
+    #[test]
+    fn round_robin_ranks_players() {
+        let players = vec![
+            Player::new("rock", Strategy::AlwaysRock),
+            Player::new("cycle", Strategy::Cycle),
+            Player::new("copy", Strategy::CopyLast),
+        ];
+        let standings = round_robin(&players, 3);
+        let names: Vec<&str> = standings.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["cycle", "rock", "copy"]);
+        assert_eq!((standings[0].wins, standings[0].draws), (2, 0));
+        assert_eq!((standings[1].wins, standings[1].draws), (0, 1));
+        assert_eq!(standings[0].score, 15 + 21);
+    }
+
+    #[test]
+    fn elimination_handles_byes() {
+        let players = vec![
+            Player::new("rock", Strategy::AlwaysRock),
+            Player::new("cycle", Strategy::Cycle),
+            Player::new("copy", Strategy::CopyLast),
+        ];
+        let standings = elimination(&players, 3);
+        let names: Vec<&str> = standings.iter().map(|s| s.name.as_str()).collect();
+        // Cycle beats rock, copy gets a bye and then loses the final
+        assert_eq!(names, vec!["cycle", "copy", "rock"]);
+        assert_eq!(standings[0].stage, 3);
+        assert_eq!(standings[1].stage, 2);
+        assert_eq!(standings[2].stage, 1);
+    }
+
+    #[test]
+    fn tournament_with_extra_guides() {
+        let guide = StrategyGuide::from_str("A Y\nB X\n").unwrap();
+        let guides = vec![
+            ("scissors".to_string(), "A Z\n".to_string()),
+            ("rock".to_string(), "C X\nC X\n".to_string()),
+        ];
+        let players = tournament_players(&guide, &guides);
+        let names: Vec<&str> = players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "guide",
+                "scissors",
+                "rock",
+                "always-rock",
+                "cycle",
+                "copy-last"
+            ]
+        );
+        assert_eq!(players[1].strategy, Strategy::Guide(vec![RPS::Scissors]));
+
+        // Paper, Rock, Paper against Scissors every round
+        let game = CyclicGame::rock_paper_scissors();
+        assert_eq!(
+            play_match(&game, &players[0], &players[1], 3),
+            (2 + 7 + 2, 9 + 3 + 9)
+        );
+        let standings = round_robin(&players[..2], 3);
+        assert_eq!(standings[0].name, "scissors");
+        assert_eq!((standings[0].wins, standings[1].losses), (1, 1));
     }
 }
@@ -1,9 +1,26 @@
+use std::fmt;
+use std::ops::{BitAnd, BitOr};
+
 pub fn exec(input: &str) {
     let res1 = testable_exec_part1(input);
     let res2 = testable_exec_part2(input);
     println!("Day 3\n\tpart 1:\t{res1}\n\tpart 2:\t{res2}");
 }
 
+/// Same as `exec` with a different number of compartments per rucksack and
+/// elves per group, reporting inputs that don't fit instead of panicking.
+pub fn exec_with(input: &str, compartments: usize, group_size: usize) {
+    let describe = |res: Result<usize, RucksackError>| match res {
+        Ok(n) => n.to_string(),
+        Err(e) => e.to_string(),
+    };
+    let res1 = describe(misplaced_priorities(input, compartments));
+    let res2 = describe(badge_priorities(input, group_size));
+    println!(
+        "Day 3 ({compartments} compartments, groups of {group_size})\n\tpart 1:\t{res1}\n\tpart 2:\t{res2}"
+    );
+}
+
 /// --- Day 3: Rucksack Reorganization ---
 ///
 /// One Elf has the important job of loading all of the rucksacks with supplies for the jungle journey. Unfortunately, that Elf didn't quite follow the packing instructions, and so a few items now need to be rearranged.
@@ -39,31 +56,7 @@ pub fn exec(input: &str) {
 ///
 /// Find the item type that appears in both compartments of each rucksack. What is the sum of the priorities of those item types?
 fn testable_exec_part1(input: &str) -> usize {
-    let res: usize = input
-        .lines()
-        .map(|line| {
-            let n = line.chars().count();
-            let (c1, c2) = line.split_at(n / 2);
-            let mut vc1: Vec<char> = c1.chars().collect();
-            let mut vc2: Vec<char> = c2.chars().collect();
-            vc1.sort();
-            vc1.dedup();
-            vc2.sort();
-            vc2.dedup();
-            let mut sum = 0;
-            for c in vc1 {
-                if vc2.contains(&c) {
-                    if c.is_ascii_lowercase() {
-                        sum += c as usize - 96;
-                    } else {
-                        sum += c as usize - 38;
-                    }
-                }
-            }
-            sum
-        })
-        .sum();
-    res
+    misplaced_priorities(input, 2).unwrap_or_else(|e| panic!("{e}"))
 }
 
 /// --- Part Two ---
@@ -94,42 +87,331 @@ fn testable_exec_part1(input: &str) -> usize {
 ///
 /// Find the item type that corresponds to the badges of each three-Elf group. What is the sum of the priorities of those item types?
 fn testable_exec_part2(input: &str) -> usize {
-    let process: Vec<Vec<char>> = input
+    badge_priorities(input, 3).unwrap_or_else(|e| panic!("{e}"))
+}
+
+#[derive(Debug, PartialEq)]
+enum RucksackError {
+    InvalidItem {
+        line: usize,
+        item: char,
+    },
+    UnevenCompartments {
+        line: usize,
+        items: usize,
+        compartments: usize,
+    },
+    IncompleteGroup {
+        group: usize,
+        size: usize,
+        expected: usize,
+    },
+    SharedItems {
+        group: usize,
+        count: usize,
+    },
+    /// A compartment count or group size of 0
+    ZeroSize {
+        what: &'static str,
+    },
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RucksackError::InvalidItem { line, item } => {
+                write!(f, "line {line}: invalid item type {item:?}")
+            }
+            RucksackError::UnevenCompartments {
+                line,
+                items,
+                compartments,
+            } => write!(
+                f,
+                "line {line}: {items} items can't be split into {compartments} equal compartments"
+            ),
+            RucksackError::IncompleteGroup {
+                group,
+                size,
+                expected,
+            } => write!(f, "group {group}: only {size} of {expected} rucksacks"),
+            RucksackError::SharedItems { group, count } => write!(
+                f,
+                "group {group}: expected exactly one shared item type, found {count}"
+            ),
+            RucksackError::ZeroSize { what } => write!(f, "{what} must be at least 1"),
+        }
+    }
+}
+
+/// Splits every rucksack into `compartments` equally sized compartments and
+/// sums the priorities of item types found in more than one of them.
+fn misplaced_priorities(input: &str, compartments: usize) -> Result<usize, RucksackError> {
+    if compartments == 0 {
+        return Err(RucksackError::ZeroSize {
+            what: "compartments",
+        });
+    }
+    let mut sum = 0;
+    for (i, line) in input.lines().enumerate() {
+        // Every item is a single ASCII letter after this, so slicing by
+        // byte is safe
+        ItemSet::from_items(line)
+            .map_err(|item| RucksackError::InvalidItem { line: i + 1, item })?;
+        if line.len() % compartments != 0 {
+            return Err(RucksackError::UnevenCompartments {
+                line: i + 1,
+                items: line.len(),
+                compartments,
+            });
+        }
+        let size = line.len() / compartments;
+        let mut seen = ItemSet::default();
+        let mut misplaced = ItemSet::default();
+        for c in 0..compartments {
+            let compartment = ItemSet::from_items(&line[c * size..(c + 1) * size]).unwrap();
+            misplaced = misplaced | (seen & compartment);
+            seen = seen | compartment;
+        }
+        sum += misplaced.priority_sum();
+    }
+    Ok(sum)
+}
+
+/// Chunks the rucksacks into groups of `group_size` and sums the priorities
+/// of each group's badge.
+fn badge_priorities(input: &str, group_size: usize) -> Result<usize, RucksackError> {
+    Ok(badges(input, group_size)?.iter().sum())
+}
+
+/// Chunks the rucksacks into groups of `group_size` and returns the priority
+/// of each group's badge, the one item type all of them carry.
+fn badges(input: &str, group_size: usize) -> Result<Vec<usize>, RucksackError> {
+    if group_size == 0 {
+        return Err(RucksackError::ZeroSize { what: "group size" });
+    }
+    let rucksacks = input
         .lines()
-        .map(|line| {
-            let mut v: Vec<char> = line.chars().collect();
-            v.sort();
-            v.dedup();
-            v
+        .enumerate()
+        .map(|(i, line)| {
+            ItemSet::from_items(line)
+                .map_err(|item| RucksackError::InvalidItem { line: i + 1, item })
         })
-        .collect();
-    let res: usize = process
-        .chunks(3)
-        .map(|slice| {
-            let c1 = slice.get(0).unwrap();
-            let c2 = slice.get(1).unwrap();
-            let c3 = slice.get(2).unwrap();
-            let mut sum = 0;
-            for c in c1 {
-                if c2.contains(&c) {
-                    if c3.contains(c) {
-                        if c.is_ascii_lowercase() {
-                            sum += c.clone() as usize - 96;
-                        } else {
-                            sum += c.clone() as usize - 38;
-                        }
-                    }
-                }
+        .collect::<Result<Vec<ItemSet>, RucksackError>>()?;
+    if rucksacks.len() % group_size != 0 {
+        return Err(RucksackError::IncompleteGroup {
+            group: rucksacks.len() / group_size + 1,
+            size: rucksacks.len() % group_size,
+            expected: group_size,
+        });
+    }
+    let mut badges = vec![];
+    for (i, group) in rucksacks.chunks(group_size).enumerate() {
+        let badge = ItemSet::intersection_of(group.iter().copied());
+        if badge.len() != 1 {
+            return Err(RucksackError::SharedItems {
+                group: i + 1,
+                count: badge.len(),
+            });
+        }
+        badges.push(badge.priority_sum());
+    }
+    Ok(badges)
+}
+
+/// Moving `count` items of type `item` from one compartment to the other,
+/// compartments are numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Move {
+    item: char,
+    count: usize,
+    from: usize,
+    to: usize,
+}
+
+/// Finds the fewest item moves that leave every item type in exactly one of
+/// the two compartments, with both compartments still the same size. Returns
+/// None when no such arrangement exists, for example when one item type
+/// makes up more than half the rucksack.
+///
+/// Each item type has to end up whole in one compartment, so this picks the
+/// item types for the first compartment whose counts add up to exactly half
+/// the rucksack while keeping as many items in place as possible. That is a
+/// knapsack over at most 52 item types and half a rucksack of capacity.
+fn plan_rucksack(line: &str) -> Option<Vec<Move>> {
+    let size = line.len() / 2;
+    let (c1, c2) = line.split_at(size);
+    // Items of each type in each compartment, indexed by priority
+    let mut counts = [[0usize; 2]; 53];
+    for (c, compartment) in [c1, c2].iter().enumerate() {
+        for p in compartment.chars().filter_map(priority) {
+            counts[p][c] += 1;
+        }
+    }
+    let types: Vec<usize> = (1..=52).filter(|&p| counts[p] != [0, 0]).collect();
+
+    // kept[i][cap] is the most items left in place when the first i types
+    // put `cap` items in the first compartment
+    let mut kept = vec![vec![None; size + 1]; types.len() + 1];
+    kept[0][0] = Some(0);
+    for (i, &p) in types.iter().enumerate() {
+        let total = counts[p][0] + counts[p][1];
+        for cap in 0..=size {
+            let second = kept[i][cap].map(|k| k + counts[p][1]);
+            let first = match cap.checked_sub(total) {
+                Some(rest) => kept[i][rest].map(|k: usize| k + counts[p][0]),
+                None => None,
+            };
+            kept[i + 1][cap] = first.max(second);
+        }
+    }
+    kept[types.len()][size]?;
+
+    let mut moves = vec![];
+    let mut cap = size;
+    for (i, &p) in types.iter().enumerate().rev() {
+        let total = counts[p][0] + counts[p][1];
+        let in_first = match cap.checked_sub(total) {
+            Some(rest) => kept[i][rest].map(|k| k + counts[p][0]) == kept[i + 1][cap],
+            None => false,
+        };
+        let (from, to) = if in_first { (2, 1) } else { (1, 2) };
+        if in_first {
+            cap -= total;
+        }
+        let count = counts[p][from - 1];
+        if count > 0 {
+            moves.push(Move {
+                item: item_type(p),
+                count,
+                from,
+                to,
+            });
+        }
+    }
+    moves.reverse();
+    Some(moves)
+}
+
+/// Prints the moves needed to fix every rucksack and the badge of every
+/// group of three.
+pub fn exec_plan(input: &str) {
+    println!("Day 3 reorganisation");
+    for (i, line) in input.lines().enumerate() {
+        if let Err(item) = ItemSet::from_items(line) {
+            println!(
+                "\trucksack {}: {}",
+                i + 1,
+                RucksackError::InvalidItem { line: i + 1, item }
+            );
+            continue;
+        }
+        if line.len() % 2 != 0 {
+            println!("\trucksack {}: odd number of items", i + 1);
+            continue;
+        }
+        match plan_rucksack(line) {
+            None => println!("\trucksack {}: no arrangement possible", i + 1),
+            Some(moves) if moves.is_empty() => println!("\trucksack {}: nothing to move", i + 1),
+            Some(moves) => {
+                let moves: Vec<String> = moves
+                    .iter()
+                    .map(|m| format!("{} {} from {} to {}", m.count, m.item, m.from, m.to))
+                    .collect();
+                println!("\trucksack {}: move {}", i + 1, moves.join(", "));
             }
-            sum
-        })
-        .sum();
-    res
+        }
+    }
+    match badges(input, 3) {
+        Ok(badges) => {
+            for (i, &badge) in badges.iter().enumerate() {
+                println!("\tgroup {}: badge {}", i + 1, item_type(badge));
+            }
+        }
+        Err(e) => println!("\tbadges: {e}"),
+    }
+}
+
+/// Lowercase item types a through z have priorities 1 through 26,
+/// uppercase item types A through Z have priorities 27 through 52.
+fn priority(item: char) -> Option<usize> {
+    match item {
+        'a'..='z' => Some(item as usize - 'a' as usize + 1),
+        'A'..='Z' => Some(item as usize - 'A' as usize + 27),
+        _ => None,
+    }
+}
+
+/// The item type with priority `p`, the inverse of `priority`.
+fn item_type(p: usize) -> char {
+    match p {
+        1..=26 => (b'a' + (p - 1) as u8) as char,
+        27..=52 => (b'A' + (p - 27) as u8) as char,
+        _ => panic!("Invalid priority: {p}"),
+    }
+}
+
+/// A set of item types packed into a u64, bit `n` is set when the item type
+/// with priority `n` is in the set. Intersection is `&` and union is `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    /// Every item type there is, priorities 1 through 52.
+    const ALL: ItemSet = ItemSet(((1 << 52) - 1) << 1);
+
+    /// Fails with the first character that isn't an item type.
+    fn from_items(items: &str) -> Result<Self, char> {
+        items
+            .chars()
+            .try_fold(ItemSet::default(), |set, item| match priority(item) {
+                Some(p) => Ok(ItemSet(set.0 | 1 << p)),
+                None => Err(item),
+            })
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Item types found in every one of `sets`, empty if there are no sets.
+    fn intersection_of(sets: impl IntoIterator<Item = ItemSet>) -> Self {
+        let mut sets = sets.into_iter().peekable();
+        if sets.peek().is_none() {
+            return ItemSet::default();
+        }
+        sets.fold(ItemSet::ALL, |acc, set| acc & set)
+    }
+
+    fn priorities(self) -> impl Iterator<Item = usize> {
+        (1..=52).filter(move |p| self.0 & (1 << p) != 0)
+    }
+
+    fn priority_sum(self) -> usize {
+        self.priorities().sum()
+    }
+}
+
+impl BitAnd for ItemSet {
+    type Output = ItemSet;
+
+    fn bitand(self, rhs: ItemSet) -> ItemSet {
+        ItemSet(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for ItemSet {
+    type Output = ItemSet;
+
+    fn bitor(self, rhs: ItemSet) -> ItemSet {
+        ItemSet(self.0 | rhs.0)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::Lcg;
 
     #[test]
     fn example_works() {
@@ -144,4 +426,231 @@ mod tests {
         let n = testable_exec_part2(input);
         assert_eq!(70, n)
     }
+
+    #[test]
+    fn priority_works() {
+        assert_eq!(priority('a'), Some(1));
+        assert_eq!(priority('z'), Some(26));
+        assert_eq!(priority('A'), Some(27));
+        assert_eq!(priority('Z'), Some(52));
+        assert_eq!(priority('1'), None);
+    }
+
+    #[test]
+    fn item_set_algebra() {
+        let a = ItemSet::from_items("abcA").unwrap();
+        let b = ItemSet::from_items("cAZ").unwrap();
+        assert_eq!((a & b).priorities().collect::<Vec<_>>(), vec![3, 27]);
+        assert_eq!(
+            (a | b).priorities().collect::<Vec<_>>(),
+            vec![1, 2, 3, 27, 52]
+        );
+        assert_eq!(
+            ItemSet::intersection_of([a, b, ItemSet::from_items("A").unwrap()]).priority_sum(),
+            27
+        );
+        assert_eq!(ItemSet::intersection_of([]), ItemSet::default());
+        assert_eq!(
+            [a, b].into_iter().fold(ItemSet::default(), |u, s| u | s),
+            a | b
+        );
+        assert_eq!(ItemSet::ALL.priority_sum(), (1..=52).sum());
+    }
+
+    #[test]
+    fn more_compartments() {
+        // ab|ca|db, a and b are both in two compartments
+        assert_eq!(misplaced_priorities("abcadb", 3), Ok(1 + 2));
+        assert_eq!(misplaced_priorities("abcadb", 6), Ok(1 + 2));
+        assert_eq!(misplaced_priorities("abcdef", 6), Ok(0));
+        assert_eq!(
+            misplaced_priorities("ab\nabc", 2),
+            Err(RucksackError::UnevenCompartments {
+                line: 2,
+                items: 3,
+                compartments: 2
+            })
+        );
+        assert_eq!(
+            misplaced_priorities("ab\na-", 2),
+            Err(RucksackError::InvalidItem { line: 2, item: '-' })
+        );
+        assert_eq!(
+            misplaced_priorities("ab", 0),
+            Err(RucksackError::ZeroSize {
+                what: "compartments"
+            })
+        );
+    }
+
+    #[test]
+    fn group_validation() {
+        let input = "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\nPmmdzqPrVvPwwTWBwg\nwMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\nttgJtRGJQctTZtZT\nCrZsJsPPZsGzwwsLwLmpwMDw";
+        assert_eq!(badge_priorities(input, 3), Ok(70));
+        assert_eq!(
+            badge_priorities(input, 4),
+            Err(RucksackError::IncompleteGroup {
+                group: 2,
+                size: 2,
+                expected: 4
+            })
+        );
+        assert_eq!(
+            badge_priorities(input, 0).unwrap_err().to_string(),
+            "group size must be at least 1"
+        );
+        assert_eq!(
+            badge_priorities("abc\nabd\nxyz", 3),
+            Err(RucksackError::SharedItems { group: 1, count: 0 })
+        );
+        assert_eq!(
+            badge_priorities("abc\nabd\nabz", 3),
+            Err(RucksackError::SharedItems { group: 1, count: 2 })
+        );
+        assert_eq!(
+            badge_priorities("abc\nabd\nabz", 3)
+                .unwrap_err()
+                .to_string(),
+            "group 1: expected exactly one shared item type, found 2"
+        );
+    }
+
+    /// Applies the moves and checks every item type ends up in one
+    /// compartment of the right size.
+    fn check_plan(line: &str, moves: &[Move]) {
+        let (c1, c2) = line.split_at(line.len() / 2);
+        let mut compartments = [c1.to_string(), c2.to_string()];
+        for m in moves {
+            for _ in 0..m.count {
+                let at = compartments[m.from - 1].find(m.item).unwrap();
+                compartments[m.from - 1].remove(at);
+                compartments[m.to - 1].push(m.item);
+            }
+        }
+        assert_eq!(compartments[0].len(), compartments[1].len());
+        let sets = compartments.map(|c| ItemSet::from_items(&c).unwrap());
+        assert_eq!(sets[0] & sets[1], ItemSet::default());
+    }
+
+    #[test]
+    fn plan_rucksack_works() {
+        for line in [
+            "vJrwpWtwJgWrhcsFMMfFFhFp",
+            "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
+            "PmmdzqPrVvPwwTWBwg",
+            "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
+            "ttgJtRGJQctTZtZT",
+            "CrZsJsPPZsGzwwsLwLmpwMDw",
+        ] {
+            let moves = plan_rucksack(line).unwrap();
+            check_plan(line, &moves);
+        }
+        // One p has to move over and something else has to make room for it
+        let moves = plan_rucksack("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        assert_eq!(moves.iter().map(|m| m.count).sum::<usize>(), 2);
+        assert_eq!(plan_rucksack("abcd"), Some(vec![]));
+        let moves = plan_rucksack("abca").unwrap();
+        check_plan("abca", &moves);
+        assert_eq!(moves.len(), 2);
+        // Three a's can't fit in a compartment of two
+        assert_eq!(plan_rucksack("aaab"), None);
+    }
+
+    #[test]
+    fn badges_works() {
+        let input = "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\nPmmdzqPrVvPwwTWBwg\nwMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\nttgJtRGJQctTZtZT\nCrZsJsPPZsGzwwsLwLmpwMDw";
+        let badges: Vec<char> = badges(input, 3)
+            .unwrap()
+            .into_iter()
+            .map(item_type)
+            .collect();
+        assert_eq!(badges, vec!['r', 'Z']);
+    }
+
+    /// Part 1 as first written, sorting both halves and checking `contains`.
+    /// The item set version has to agree with it.
+    fn part1_naive(input: &str) -> usize {
+        input
+            .lines()
+            .map(|line| {
+                let (c1, c2) = line.split_at(line.len() / 2);
+                let mut vc1: Vec<char> = c1.chars().collect();
+                let mut vc2: Vec<char> = c2.chars().collect();
+                vc1.sort();
+                vc1.dedup();
+                vc2.sort();
+                vc2.dedup();
+                vc1.iter()
+                    .filter(|c| vc2.contains(c))
+                    .map(|&c| priority(c).unwrap())
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// `groups` groups of three rucksacks with `len` items each. Every group
+    /// carries one planted badge and otherwise splits the remaining item
+    /// types between its rucksacks, so nothing else is shared by all three.
+    /// Returns the input and the sum of the badges' priorities.
+    fn generate_groups(groups: usize, len: usize) -> (String, usize) {
+        let letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+        let mut rng = Lcg::new(7);
+        let mut out = String::new();
+        let mut badge_sum = 0;
+        for _ in 0..groups {
+            let badge = letters[rng.below(52)];
+            badge_sum += priority(badge).unwrap();
+            let others: Vec<char> = letters.iter().copied().filter(|&c| c != badge).collect();
+            let rotation = rng.below(3);
+            for k in 0..3 {
+                let pool: Vec<char> = others
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| (i + rotation) % 3 == k)
+                    .map(|(_, &c)| c)
+                    .collect();
+                let mut rucksack: Vec<char> =
+                    (0..len - 1).map(|_| pool[rng.below(pool.len())]).collect();
+                rucksack.insert(rng.below(len), badge);
+                out.extend(rucksack);
+                out.push('\n');
+            }
+        }
+        (out, badge_sum)
+    }
+
+    #[test]
+    fn matches_naive_on_generated_input() {
+        let (input, badge_sum) = generate_groups(334, 40);
+        assert_eq!(testable_exec_part1(&input), part1_naive(&input));
+        assert_eq!(testable_exec_part2(&input), badge_sum);
+    }
+
+    /// Times both parts on 70k generated groups of rucksacks, with part 1 also timed
+    /// the old way for comparison. Try
+    /// `cargo test --release -- --ignored --nocapture bench_item_sets`.
+    #[test]
+    #[ignore]
+    fn bench_item_sets() {
+        use std::time::Instant;
+
+        let (input, badge_sum) = generate_groups(70_000, 48);
+        let now = Instant::now();
+        let naive = part1_naive(&input);
+        println!(
+            "sort and contains, {} bytes: {:?}",
+            input.len(),
+            now.elapsed()
+        );
+        let now = Instant::now();
+        assert_eq!(testable_exec_part1(&input), naive);
+        println!("item sets, {} bytes: {:?}", input.len(), now.elapsed());
+        let now = Instant::now();
+        assert_eq!(testable_exec_part2(&input), badge_sum);
+        println!(
+            "item sets part 2, {} bytes: {:?}",
+            input.len(),
+            now.elapsed()
+        );
+    }
 }
@@ -1,3 +1,7 @@
+use std::fmt;
+use std::ops::{BitAnd, BitOr};
+use std::str::FromStr;
+
 /// --- Day 4: Camp Cleanup ---
 ///
 /// Space needs to be cleared before the last supplies can be unloaded from the ships, and so several Elves have been assigned the job of cleaning up sections of the camp. Every section has a unique ID number, and each Elf is assigned a range of section IDs.
@@ -51,89 +55,333 @@ pub fn exec(input: &str) {
 }
 
 fn testable_exec_1(input: &str) -> u32 {
-    let x: u32 = input
+    let pairs = parse_pairs(input);
+    pairs
+        .iter()
+        .filter(|(a, b)| a.contains(b) || b.contains(a))
+        .count() as u32
+}
+
+fn testable_exec_2(input: &str) -> u32 {
+    let pairs = parse_pairs(input);
+    pairs.iter().filter(|(a, b)| a.overlaps(b)).count() as u32
+}
+
+fn parse_pairs(input: &str) -> Vec<(Interval, Interval)> {
+    input
         .lines()
         .map(|line| {
             // Safety: We know we'll have a clean input, unwrapping the
             // option is fine.
             let (elf1, elf2) = line.split_once(",").unwrap();
-            let (e1_lo, e1_hi) = elf1.split_once("-").unwrap();
-            let (e2_lo, e2_hi) = elf2.split_once("-").unwrap();
-            let elf1_lower: u32 = e1_lo.parse::<u32>().unwrap();
-            let elf1_higher: u32 = e1_hi.parse::<u32>().unwrap();
-            let elf2_lower: u32 = e2_lo.parse::<u32>().unwrap();
-            let elf2_higher: u32 = e2_hi.parse::<u32>().unwrap();
-
-            let mut sum = 0;
-            if elf1_higher > elf2_higher {
-                if elf1_lower <= elf2_lower {
-                    sum = 1;
-                }
+            match (elf1.parse(), elf2.parse()) {
+                (Ok(a), Ok(b)) => (a, b),
+                (Err(e), _) | (_, Err(e)) => panic!("{e}"),
             }
+        })
+        .collect()
+}
 
-            if elf1_higher <= elf2_higher {
-                if elf1_lower >= elf2_lower {
-                    sum = 1;
-                }
+/// Every elf's assignment taken together: the merged sections covered by at
+/// least one elf, those covered by more than `more_than` elves, and the gaps
+/// left between the lowest and highest section.
+#[derive(Debug, PartialEq)]
+struct Coverage {
+    more_than: usize,
+    covered: Vec<Interval>,
+    crowded: Vec<Interval>,
+    gaps: Vec<Interval>,
+}
 
-                if elf1_lower < elf2_lower && elf2_higher == elf1_higher {
-                    sum = 1;
-                }
-                // Corner case
-                if elf2_higher == elf2_lower && elf2_higher == elf1_higher {
-                    sum = 1;
-                }
+impl Coverage {
+    fn total(intervals: &[Interval]) -> u64 {
+        intervals.iter().map(Interval::len).sum()
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |intervals: &[Interval]| {
+            let list: Vec<String> = intervals.iter().map(|i| i.to_string()).collect();
+            if list.is_empty() {
+                "none".to_string()
+            } else {
+                list.join(", ")
             }
-            sum
+        };
+        writeln!(
+            f,
+            "\tcovered:\t{} sections ({})",
+            Self::total(&self.covered),
+            list(&self.covered)
+        )?;
+        writeln!(
+            f,
+            "\tby more than {}:\t{} sections ({})",
+            self.more_than,
+            Self::total(&self.crowded),
+            list(&self.crowded)
+        )?;
+        write!(
+            f,
+            "\tgaps:\t\t{} sections ({})",
+            Self::total(&self.gaps),
+            list(&self.gaps)
+        )
+    }
+}
+
+/// Sweeps over the start and end of every assignment, keeping count of how
+/// many elves are on the current section.
+fn coverage(pairs: &[(Interval, Interval)], more_than: usize) -> Coverage {
+    // Ends are stored one past the interval so that u32::MAX still fits.
+    let mut events: Vec<(u64, isize)> = pairs
+        .iter()
+        .flat_map(|(a, b)| [a, b])
+        .flat_map(|i| [(i.lo as u64, 1), (i.hi as u64 + 1, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut covered = vec![];
+    let mut crowded = vec![];
+    let mut depth = 0;
+    for (i, &(position, change)) in events.iter().enumerate() {
+        depth += change;
+        let Some(&(next, _)) = events.get(i + 1) else {
+            break;
+        };
+        if next == position || depth == 0 {
+            continue;
+        }
+        let segment = Interval {
+            lo: position as u32,
+            hi: (next - 1) as u32,
+        };
+        push_merged(&mut covered, segment);
+        if depth as usize > more_than {
+            push_merged(&mut crowded, segment);
+        }
+    }
+    let gaps = covered
+        .windows(2)
+        .map(|w| Interval {
+            lo: w[0].hi + 1,
+            hi: w[1].lo - 1,
         })
-        .sum();
+        .collect();
 
-    x
+    Coverage {
+        more_than,
+        covered,
+        crowded,
+        gaps,
+    }
 }
 
-fn testable_exec_2(input: &str) -> u32 {
-    let x: u32 = input
-        .lines()
-        .map(|line| {
-            // Safety: We know we'll have a clean input, unwrapping the
-            // option is fine.
-            let (elf1, elf2) = line.split_once(",").unwrap();
-            let (e1_lo, e1_hi) = elf1.split_once("-").unwrap();
-            let (e2_lo, e2_hi) = elf2.split_once("-").unwrap();
-            let elf1_lower: u32 = e1_lo.parse::<u32>().unwrap();
-            let elf1_higher: u32 = e1_hi.parse::<u32>().unwrap();
-            let elf2_lower: u32 = e2_lo.parse::<u32>().unwrap();
-            let elf2_higher: u32 = e2_hi.parse::<u32>().unwrap();
-
-            let mut sum = 0;
-
-            if elf1_higher > elf2_higher {
-                if elf1_lower <= elf2_higher {
-                    sum = 1;
-                }
-            }
+fn push_merged(intervals: &mut Vec<Interval>, interval: Interval) {
+    match intervals.last_mut() {
+        Some(last) => match *last | interval {
+            Some(merged) => *last = merged,
+            None => intervals.push(interval),
+        },
+        None => intervals.push(interval),
+    }
+}
 
-            if elf1_higher < elf2_higher {
-                if elf2_lower <= elf1_higher {
-                    sum = 1;
+pub fn exec_coverage(input: &str, more_than: usize) {
+    let coverage = coverage(&parse_pairs(input), more_than);
+    println!("Day 4 coverage\n{coverage}");
+}
+
+/// Maps section IDs onto diagram columns. When the sections from 1 fit in
+/// `width` the diagram starts at 1 like the puzzle's, otherwise it starts
+/// at the lowest section drawn. Past `width` sections every column covers a
+/// bucket of sections.
+struct Scale {
+    first: u32,
+    last: u32,
+    per_cell: u64,
+}
+
+impl Scale {
+    fn new(pairs: &[(Interval, Interval)], width: usize) -> Scale {
+        let width = width.max(1) as u64;
+        let intervals = pairs.iter().flat_map(|(a, b)| [a, b]);
+        let lowest = intervals.clone().map(|i| i.lo).min().unwrap_or(1);
+        let last = intervals.map(|i| i.hi).max().unwrap_or(1);
+        let sections = |first: u32| (last - first) as u64 + 1;
+        let first = match sections(lowest.min(1)) <= width {
+            true => lowest.min(1),
+            false => lowest,
+        };
+        Scale {
+            first,
+            last,
+            per_cell: sections(first).div_ceil(width),
+        }
+    }
+
+    fn row(&self, interval: &Interval) -> String {
+        if self.per_cell == 1 {
+            return (self.first..=self.last)
+                .map(
+                    |section| match interval.lo <= section && section <= interval.hi {
+                        true => char::from_digit(section % 10, 10).unwrap(),
+                        false => '.',
+                    },
+                )
+                .collect();
+        }
+        let sections = (self.last - self.first) as u64 + 1;
+        (0..sections.div_ceil(self.per_cell))
+            .map(|cell| {
+                let lo = self.first as u64 + cell * self.per_cell;
+                let hi = (lo + self.per_cell - 1).min(self.last as u64);
+                Interval {
+                    lo: lo as u32,
+                    hi: hi as u32,
                 }
-            }
+            })
+            .map(|cell| match cell.overlaps(interval) {
+                true => '#',
+                false => '.',
+            })
+            .collect()
+    }
+}
 
-            if elf1_higher == elf2_higher {
-                // All variations on this arrangement end in an overlap
-                sum = 1;
-            }
+/// Draws each pair the way the puzzle does, `.234.....  2-4`, with a blank
+/// line between pairs. The first row of a pair is marked when one assignment
+/// contains the other or when they overlap.
+fn render(pairs: &[(Interval, Interval)], width: usize) -> String {
+    let scale = Scale::new(pairs, width);
+    let label_width = pairs
+        .iter()
+        .flat_map(|(a, b)| [a, b])
+        .map(|i| i.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let line = |interval: &Interval, mark: &str| {
+        let line = format!(
+            "{}  {:<label_width$}  {mark}",
+            scale.row(interval),
+            interval.to_string()
+        );
+        line.trim_end().to_string()
+    };
+    pairs
+        .iter()
+        .map(|(a, b)| {
+            let mark = if a.contains(b) || b.contains(a) {
+                "<- contains"
+            } else if a.overlaps(b) {
+                "<- overlaps"
+            } else {
+                ""
+            };
+            format!("{}\n{}\n", line(a, mark), line(b, ""))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn exec_draw(input: &str, width: usize) {
+    println!("Day 4 diagram\n{}", render(&parse_pairs(input), width));
+}
+
+/// An inclusive range of section IDs. Intersection is `&` and union is `|`,
+/// both give None when the result isn't a single interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval {
+    lo: u32,
+    hi: u32,
+}
+
+#[derive(Debug, PartialEq)]
+struct ParseIntervalError(String);
+
+impl fmt::Display for ParseIntervalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid section assignment: {}", self.0)
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.lo, self.hi)
+    }
+}
+
+impl FromStr for Interval {
+    type Err = ParseIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseIntervalError(s.to_string());
+        let (lo, hi) = s.trim().split_once('-').ok_or_else(error)?;
+        let lo = lo.parse::<u32>().map_err(|_| error())?;
+        let hi = hi.parse::<u32>().map_err(|_| error())?;
+        if lo > hi {
+            return Err(error());
+        }
+        Ok(Interval { lo, hi })
+    }
+}
+
+impl Interval {
+    fn len(&self) -> u64 {
+        (self.hi - self.lo) as u64 + 1
+    }
+
+    fn contains(&self, other: &Interval) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi
+    }
 
-            sum
+    fn overlaps(&self, other: &Interval) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let lo = self.lo.max(other.lo);
+        let hi = self.hi.min(other.hi);
+        (lo <= hi).then_some(Interval { lo, hi })
+    }
+
+    /// Overlapping or touching intervals merge into one, anything else
+    /// leaves a gap and is None.
+    fn union(&self, other: &Interval) -> Option<Interval> {
+        let (first, second) = if self.lo <= other.lo {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        (second.lo <= first.hi.saturating_add(1)).then_some(Interval {
+            lo: first.lo,
+            hi: first.hi.max(second.hi),
         })
-        .sum();
+    }
+}
+
+impl BitAnd for Interval {
+    type Output = Option<Interval>;
+
+    fn bitand(self, rhs: Interval) -> Option<Interval> {
+        self.intersection(&rhs)
+    }
+}
 
-    x
+impl BitOr for Interval {
+    type Output = Option<Interval>;
+
+    fn bitor(self, rhs: Interval) -> Option<Interval> {
+        self.union(&rhs)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::Lcg;
+    use std::collections::BTreeSet;
 
     #[test]
     fn part1_example_works() {
@@ -151,4 +399,197 @@ mod tests {
         let input = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8\n4-6,6-6";
         assert_eq!(testable_exec_2(input), 5)
     }
+
+    #[test]
+    fn interval_parses() {
+        assert_eq!("2-4".parse(), Ok(Interval { lo: 2, hi: 4 }));
+        assert_eq!("6-6".parse(), Ok(Interval { lo: 6, hi: 6 }));
+        assert!("4-2".parse::<Interval>().is_err());
+        assert!("4".parse::<Interval>().is_err());
+        assert!("a-2".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn interval_algebra() {
+        let i = |lo, hi| Interval { lo, hi };
+        assert!(i(2, 8).contains(&i(3, 7)));
+        assert!(!i(3, 7).contains(&i(2, 8)));
+        assert!(i(5, 7).overlaps(&i(7, 9)));
+        assert!(!i(2, 3).overlaps(&i(4, 5)));
+        assert_eq!(i(2, 6) & i(4, 8), Some(i(4, 6)));
+        assert_eq!(i(2, 3) & i(4, 5), None);
+        assert_eq!(i(2, 3) | i(4, 5), Some(i(2, 5)));
+        assert_eq!(i(2, 3) | i(5, 6), None);
+        assert_eq!(i(4, 8) | i(2, 6), Some(i(2, 8)));
+    }
+
+    fn sections(interval: &Interval) -> BTreeSet<u32> {
+        (interval.lo..=interval.hi).collect()
+    }
+
+    /// Checks every operation against plain sets of section IDs for all
+    /// intervals within 0-12.
+    #[test]
+    fn interval_matches_brute_force() {
+        let intervals: Vec<Interval> = (0..=12)
+            .flat_map(|lo| (lo..=12).map(move |hi| Interval { lo, hi }))
+            .collect();
+        for a in &intervals {
+            for b in &intervals {
+                let (sa, sb) = (sections(a), sections(b));
+                assert_eq!(a.contains(b), sb.is_subset(&sa));
+                assert_eq!(a.overlaps(b), !sa.is_disjoint(&sb));
+
+                let both: BTreeSet<u32> = sa.intersection(&sb).copied().collect();
+                assert_eq!(
+                    a.intersection(b).map(|i| sections(&i)).unwrap_or_default(),
+                    both
+                );
+
+                let either: BTreeSet<u32> = sa.union(&sb).copied().collect();
+                let contiguous =
+                    either.len() as u32 == either.last().unwrap() - either.first().unwrap() + 1;
+                match a.union(b) {
+                    Some(u) => assert_eq!(sections(&u), either),
+                    None => assert!(!contiguous),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn coverage_example() {
+        let pairs = parse_pairs("2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8");
+        let report = coverage(&pairs, 4);
+        assert_eq!(report.covered, vec![Interval { lo: 2, hi: 9 }]);
+        assert_eq!(report.crowded, vec![Interval { lo: 3, hi: 7 }]);
+        assert!(report.gaps.is_empty());
+
+        let pairs = parse_pairs("1-2,8-9\n4-4,3-3\n11-20,20-20");
+        let report = coverage(&pairs, 1);
+        assert_eq!(Coverage::total(&report.covered), 16);
+        assert_eq!(report.crowded, vec![Interval { lo: 20, hi: 20 }]);
+        assert_eq!(
+            report.gaps,
+            vec![Interval { lo: 5, hi: 7 }, Interval { lo: 10, hi: 10 }]
+        );
+    }
+
+    #[test]
+    fn coverage_at_the_edges() {
+        let pairs = parse_pairs("0-0,4294967295-4294967295");
+        let report = coverage(&pairs, 0);
+        assert_eq!(Coverage::total(&report.covered), 2);
+        assert_eq!(Coverage::total(&report.gaps), u32::MAX as u64 - 1);
+    }
+
+    /// Compares the sweep against counting elves on every single section.
+    #[test]
+    fn coverage_matches_brute_force() {
+        let mut rng = Lcg::new(3);
+        let mut random = |n: u32| rng.below(n as usize) as u32;
+        for _ in 0..200 {
+            let pairs: Vec<(Interval, Interval)> = (0..random(8) + 1)
+                .map(|_| {
+                    let mut interval = || {
+                        let lo = random(60);
+                        Interval {
+                            lo,
+                            hi: lo + random(10),
+                        }
+                    };
+                    (interval(), interval())
+                })
+                .collect();
+            let more_than = random(4) as usize;
+
+            let mut elves = [0; 70];
+            for (a, b) in &pairs {
+                for section in sections(a).into_iter().chain(sections(b)) {
+                    elves[section as usize] += 1;
+                }
+            }
+            let in_any = |intervals: &[Interval], section: u32| {
+                intervals.iter().any(|i| i.lo <= section && section <= i.hi)
+            };
+            let first = elves.iter().position(|&n| n > 0).unwrap() as u32;
+            let last = elves.iter().rposition(|&n| n > 0).unwrap() as u32;
+
+            let report = coverage(&pairs, more_than);
+            for section in 0..70 {
+                let n = elves[section as usize];
+                assert_eq!(in_any(&report.covered, section), n > 0);
+                assert_eq!(in_any(&report.crowded, section), n > more_than);
+                let gap = n == 0 && first < section && section < last;
+                assert_eq!(in_any(&report.gaps, section), gap);
+            }
+            // Merged means no two intervals could be joined.
+            for w in report.covered.windows(2) {
+                assert_eq!(w[0] | w[1], None);
+            }
+        }
+    }
+
+    #[test]
+    fn render_example() {
+        let pairs = parse_pairs("2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8");
+        let expected = "\
+.234.....  2-4
+.....678.  6-8
+
+.23......  2-3
+...45....  4-5
+
+....567..  5-7  <- overlaps
+......789  7-9
+
+.2345678.  2-8  <- contains
+..34567..  3-7
+
+.....6...  6-6  <- contains
+...456...  4-6
+
+.23456...  2-6  <- overlaps
+...45678.  4-8
+";
+        assert_eq!(render(&pairs, 80), expected);
+    }
+
+    #[test]
+    fn render_scaled() {
+        let pairs = parse_pairs("1-50,51-100\n0-10,5-99");
+        let expected = "\
+#####.....  1-50
+....######  51-100
+
+#.........  0-10    <- overlaps
+##########  5-99
+";
+        // 101 sections over at most 10 columns is 11 sections a column, marks
+        // still come from the exact assignments.
+        assert_eq!(render(&pairs, 10), expected);
+    }
+
+    #[test]
+    fn render_starts_at_the_lowest_section() {
+        let pairs = parse_pairs("90-99,95-99");
+        assert_eq!(
+            render(&pairs, 80),
+            "0123456789  90-99  <- contains\n.....56789  95-99\n"
+        );
+
+        // 2000 sections over 10 columns, each column covers 200.
+        let pairs = parse_pairs("1000-1999,1500-2999");
+        assert_eq!(
+            render(&pairs, 10),
+            "#####.....  1000-1999  <- overlaps\n..########  1500-2999\n"
+        );
+    }
+
+    #[test]
+    fn render_full_range_in_one_column() {
+        let pairs = parse_pairs("0-4294967295,7-7");
+        assert_eq!(render(&pairs, 1), "#  0-4294967295  <- contains\n#  7-7\n");
+        assert_eq!(render(&pairs, 0), render(&pairs, 1));
+    }
 }
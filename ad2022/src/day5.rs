@@ -1,5 +1,9 @@
 use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
 use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
 /// --- Day 5: Supply Stacks ---
 ///
@@ -312,6 +316,256 @@ fn chunk_description(desc: &str) -> Vec<Vec<char>> {
     stacks
 }
 
+/// Which crane is doing the lifting, part 1 moves crates one at a time
+/// while part 2 moves them all at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crane {
+    CrateMover9000,
+    CrateMover9001,
+}
+
+impl FromStr for Crane {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "9000" => Ok(Crane::CrateMover9000),
+            "9001" => Ok(Crane::CrateMover9001),
+            _ => Err(format!("unknown crane model: {s}")),
+        }
+    }
+}
+
+/// Splits the puzzle input into the starting stacks and the list of moves.
+fn parse_procedure(input: &str) -> (Vec<Vec<char>>, Vec<Instruction>) {
+    match input.split_once("\n\n") {
+        Some((stack_description, instructions)) => {
+            let stacks = chunk_description(stack_description);
+            let instructions = instructions
+                .lines()
+                .map(|il| match Instruction::from_str(il) {
+                    Ok(instruction) => instruction,
+                    Err(e) => panic!("{}", e),
+                })
+                .collect();
+            (stacks, instructions)
+        }
+        None => panic!("Couldn't split description and instructions"),
+    }
+}
+
+/// Draws the stacks the same way the puzzle description does, crates on
+/// top of each other with the stack numbers underneath.
+fn render_stacks(stacks: &[Vec<char>]) -> String {
+    let height = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut frame = String::new();
+    for row in (0..height).rev() {
+        let line: Vec<String> = stacks
+            .iter()
+            .map(|stack| match stack.get(row) {
+                Some(c) => format!("[{c}]"),
+                None => "   ".to_string(),
+            })
+            .collect();
+        frame.push_str(line.join(" ").trim_end());
+        frame.push('\n');
+    }
+    let names: Vec<String> = (1..=stacks.len()).map(|n| format!(" {n} ")).collect();
+    frame.push_str(names.join(" ").trim_end());
+    frame.push('\n');
+    frame
+}
+
+/// Runs the procedure and returns a drawing of the stacks before the first
+/// move and after every move.
+fn animation_frames(input: &str, crane: Crane) -> Vec<String> {
+    let (mut stacks, instructions) = parse_procedure(input);
+    let mut frames = vec![render_stacks(&stacks)];
+    for instruction in instructions {
+        match crane {
+            Crane::CrateMover9000 => process_instruction(&instruction, &mut stacks),
+            Crane::CrateMover9001 => process_instruction_part2(&instruction, &mut stacks),
+        }
+        frames.push(render_stacks(&stacks));
+    }
+    frames
+}
+
+/// Redraws the stacks in the terminal after each move, waiting `delay`
+/// between frames.
+pub fn animate(input: &str, crane: Crane, delay: Duration) {
+    let frames = animation_frames(input, crane);
+    let total = frames.len() - 1;
+    for (step, frame) in frames.iter().enumerate() {
+        // Clear the screen and move the cursor back to the top left corner
+        print!("\x1b[2J\x1b[H");
+        println!("Day 5 ({crane:?}) step {step}/{total}\n\n{frame}");
+        thread::sleep(delay);
+    }
+}
+
+/// Headless variant of `animate`, writes every frame to `path` instead.
+pub fn write_frames(input: &str, crane: Crane, path: &str) -> io::Result<()> {
+    let frames = animation_frames(input, crane);
+    let total = frames.len() - 1;
+    let mut file = File::create(path)?;
+    for (step, frame) in frames.iter().enumerate() {
+        writeln!(file, "step {step}/{total}\n{frame}")?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct ReverseProcedureError {
+    step: usize,
+    stack: usize,
+}
+
+impl fmt::Display for ReverseProcedureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cannot undo step {}, stack {} does not hold enough crates",
+            self.step, self.stack
+        )
+    }
+}
+
+/// What is wrong with a drawing of the final stacks.
+#[derive(Debug, PartialEq)]
+enum LayoutError {
+    MissingStackNumbers,
+    /// A crate on `line`, counting from the top, sits right of the last
+    /// numbered stack
+    CrateOutsideStacks {
+        line: usize,
+        stack: usize,
+    },
+    TooFewStacks {
+        stacks: usize,
+        needed: usize,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutError::MissingStackNumbers => {
+                write!(f, "final layout has no row of stack numbers")
+            }
+            LayoutError::CrateOutsideStacks { line, stack } => write!(
+                f,
+                "final layout line {line}: crate in stack {stack}, which is not numbered"
+            ),
+            LayoutError::TooFewStacks { stacks, needed } => write!(
+                f,
+                "final layout has {stacks} stacks but the procedure uses {needed}"
+            ),
+        }
+    }
+}
+
+/// Like `chunk_description`, but for drawings that didn't come from the
+/// puzzle and may be malformed.
+fn parse_layout(desc: &str) -> Result<Vec<Vec<char>>, LayoutError> {
+    let mut desc_iter = desc.lines().rev().skip_while(|line| line.trim().is_empty());
+    let numbers = desc_iter.next().ok_or(LayoutError::MissingStackNumbers)?;
+    let stack_count = numbers
+        .split_whitespace()
+        .map(|n| n.parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|_| LayoutError::MissingStackNumbers)?
+        .len();
+    if stack_count == 0 {
+        return Err(LayoutError::MissingStackNumbers);
+    }
+
+    let mut stacks: Vec<Vec<char>> = vec![vec![]; stack_count];
+    let lines: Vec<&str> = desc_iter.collect();
+    for (from_bottom, line) in lines.iter().enumerate() {
+        for (i, c) in line.chars().skip(1).step_by(4).enumerate() {
+            if c.is_whitespace() {
+                continue;
+            }
+            match stacks.get_mut(i) {
+                Some(stack) => stack.push(c),
+                None => {
+                    return Err(LayoutError::CrateOutsideStacks {
+                        line: lines.len() - from_bottom,
+                        stack: i + 1,
+                    })
+                }
+            }
+        }
+    }
+    Ok(stacks)
+}
+
+/// Undoes a single move by taking the crates back off the `to` stack and
+/// putting them on the `from` stack.
+fn unprocess_instruction(
+    instruction: &Instruction,
+    stacks: &mut [Vec<char>],
+    crane: Crane,
+) -> Result<(), usize> {
+    match instruction {
+        Instruction::Move { count, from, to } => {
+            if *from == 0 || *from > stacks.len() {
+                return Err(*from);
+            }
+            let to_stack = stacks.get_mut(to - 1).ok_or(*to)?;
+            if to_stack.len() < *count {
+                return Err(*to);
+            }
+            let mut lifted = to_stack.split_off(to_stack.len() - count);
+            // The CrateMover 9000 reversed the order of the crates on the way
+            // over, so it has to reverse them again on the way back.
+            if crane == Crane::CrateMover9000 {
+                lifted.reverse();
+            }
+            stacks[from - 1].extend(lifted);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the procedure backwards from `final_stacks` and returns the
+/// starting stacks that would have produced it.
+fn solve_initial_stacks(
+    instructions: &[Instruction],
+    final_stacks: Vec<Vec<char>>,
+    crane: Crane,
+) -> Result<Vec<Vec<char>>, ReverseProcedureError> {
+    let mut stacks = final_stacks;
+    for (i, instruction) in instructions.iter().enumerate().rev() {
+        unprocess_instruction(instruction, &mut stacks, crane)
+            .map_err(|stack| ReverseProcedureError { step: i + 1, stack })?;
+    }
+    Ok(stacks)
+}
+
+/// Takes the puzzle input for its instructions and a drawing of the desired
+/// final stacks, and draws the starting stacks the procedure would need.
+pub fn reverse_procedure(input: &str, final_layout: &str, crane: Crane) -> Result<String, String> {
+    let (_, instructions) = parse_procedure(input);
+    let final_stacks = parse_layout(final_layout).map_err(|e| e.to_string())?;
+    let needed = instructions
+        .iter()
+        .map(|Instruction::Move { from, to, .. }| *from.max(to))
+        .max()
+        .unwrap_or(0);
+    if final_stacks.len() < needed {
+        let e = LayoutError::TooFewStacks {
+            stacks: final_stacks.len(),
+            needed,
+        };
+        return Err(e.to_string());
+    }
+    solve_initial_stacks(&instructions, final_stacks, crane)
+        .map(|stacks| render_stacks(&stacks))
+        .map_err(|e| e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -390,4 +644,100 @@ mod tests {
             "stack2 != restack2"
         )
     }
+
+    #[test]
+    fn render_stacks_works() {
+        let stacks = vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']];
+        assert_eq!(
+            render_stacks(&stacks),
+            "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n"
+        );
+    }
+
+    #[test]
+    fn animation_frames_follow_example() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+        let frames = animation_frames(input, Crane::CrateMover9000);
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[1], "[D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n");
+        assert_eq!(
+            frames[4],
+            "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3\n"
+        );
+
+        let frames = animation_frames(input, Crane::CrateMover9001);
+        assert_eq!(
+            frames[4],
+            "        [D]\n        [N]\n        [Z]\n[M] [C] [P]\n 1   2   3\n"
+        );
+    }
+
+    #[test]
+    fn reverse_procedure_restores_example() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+        let (start, instructions) = parse_procedure(input);
+        for crane in [Crane::CrateMover9000, Crane::CrateMover9001] {
+            let mut stacks = start.clone();
+            for instruction in &instructions {
+                match crane {
+                    Crane::CrateMover9000 => process_instruction(instruction, &mut stacks),
+                    Crane::CrateMover9001 => process_instruction_part2(instruction, &mut stacks),
+                }
+            }
+            assert_eq!(
+                solve_initial_stacks(&instructions, stacks, crane),
+                Ok(start.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn reverse_procedure_draws_start() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+        let final_layout = "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3\n";
+        assert_eq!(
+            reverse_procedure(input, final_layout, Crane::CrateMover9000),
+            Ok("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n".to_string())
+        );
+    }
+
+    #[test]
+    fn reverse_procedure_rejects_short_stack() {
+        let instructions = vec![Instruction::Move {
+            count: 2,
+            from: 1,
+            to: 2,
+        }];
+        let stacks = vec![vec![], vec!['A']];
+        assert_eq!(
+            solve_initial_stacks(&instructions, stacks, Crane::CrateMover9001),
+            Err(ReverseProcedureError { step: 1, stack: 2 })
+        );
+    }
+
+    #[test]
+    fn reverse_procedure_rejects_malformed_layout() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2\n";
+        assert_eq!(
+            parse_layout("[A] [B]\n 1\n"),
+            Err(LayoutError::CrateOutsideStacks { line: 1, stack: 2 })
+        );
+        assert_eq!(
+            parse_layout("[A] [B]\n"),
+            Err(LayoutError::MissingStackNumbers)
+        );
+        assert_eq!(parse_layout("\n"), Err(LayoutError::MissingStackNumbers));
+        assert_eq!(
+            parse_layout("[A]\n[B] [C]\n 1   2\n"),
+            Ok(vec![vec!['B', 'A'], vec!['C']])
+        );
+        assert_eq!(
+            reverse_procedure(input, "[A] [B]\n 1\n", Crane::CrateMover9000),
+            Err("final layout line 1: crate in stack 2, which is not numbered".to_string())
+        );
+        assert_eq!(
+            reverse_procedure(input, "[A] [B]\n 1   2\n", Crane::CrateMover9000),
+            Err("final layout has 2 stacks but the procedure uses 3".to_string())
+        );
+    }
 }
@@ -1,3 +1,8 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufReader, Read, Write};
+use std::str::FromStr;
+
 /// --- Day 6: Tuning Trouble ---
 ///
 /// The preparations are finally complete; you and the Elves leave camp on foot and begin to make your way toward the star fruit grove.
@@ -47,88 +52,632 @@
 /// How many characters need to be processed before the first start-of-message marker is detected?
 
 pub fn exec(input: &str) {
-    let res1 = testable_exec_part1(input);
-    let res2 = testable_exec_part2(input);
+    let res1 = describe(testable_exec_part1(input));
+    let res2 = describe(testable_exec_part2(input));
 
     println!("Day 6\n\tpart 1:\t{res1}\n\tpart 2:\t{res2}");
 }
 
-fn testable_exec_part1(input: &str) -> usize {
+/// Same as `exec` but counting in `unit`, and optionally treating upper and
+/// lower case as the same symbol.
+pub fn exec_with(input: &str, unit: Unit, ignore_case: bool) {
+    let input = input.trim_end_matches(['\n', '\r']);
+    let find = |marker_len| match (unit, ignore_case) {
+        (Unit::Bytes, false) => find_marker_by(marker_len, input.bytes(), |a, b| a == b),
+        (Unit::Bytes, true) => find_marker_by(marker_len, input.bytes(), |a: u8, b: u8| {
+            a.eq_ignore_ascii_case(&b)
+        }),
+        (Unit::Chars, false) => find_marker_by(marker_len, input.chars(), |a, b| a == b),
+        (Unit::Chars, true) => find_marker_by(marker_len, input.chars(), |a: char, b: char| {
+            a.to_lowercase().eq(b.to_lowercase())
+        }),
+    };
+    let res1 = describe(find(4));
+    let res2 = describe(find(14));
+    let case = if ignore_case { ", ignoring case" } else { "" };
+
+    println!("Day 6 ({unit:?}{case})\n\tpart 1:\t{res1}\n\tpart 2:\t{res2}");
+}
+
+fn describe(res: Option<usize>) -> String {
+    match res {
+        Some(n) => n.to_string(),
+        None => "no marker found".to_string(),
+    }
+}
+
+fn testable_exec_part1(input: &str) -> Option<usize> {
     find_start_of_markers(4, input)
 }
-fn testable_exec_part2(input: &str) -> usize {
+fn testable_exec_part2(input: &str) -> Option<usize> {
     find_start_of_markers(14, input)
 }
 
 /// Generalized function for both problems.
-/// Returns 0 on no marker found, not great.
-fn find_start_of_markers(marker_len: usize, input: &str) -> usize {
-    for i in 0..(input.len() - marker_len) {
-        let input_iter = input.chars().skip(i);
-        let x: Vec<char> = input_iter.take(marker_len).collect();
-        // Keep original just in case
-        let mut y = x.clone();
-        y.sort();
-        let mut dupes = false;
-        for j in 1..y.len() {
-            if y[j - 1] == y[j] {
-                dupes = true;
-                break;
+/// Returns None if the datastream ends before a marker shows up, a trailing
+/// newline is not part of the datastream.
+fn find_start_of_markers(marker_len: usize, input: &str) -> Option<usize> {
+    if marker_len == 0 {
+        return Some(0);
+    }
+    let input = input.trim_end_matches(['\n', '\r']);
+    let mut detector = MarkerDetector::new(marker_len);
+    input.bytes().find_map(|b| detector.push(b))
+}
+
+/// What marker positions are counted in. The puzzle input is ASCII so both
+/// agree there, for anything else a char can span several bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Bytes,
+    Chars,
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytes" => Ok(Unit::Bytes),
+            "chars" => Ok(Unit::Chars),
+            _ => Err(format!("unknown unit: {s}")),
+        }
+    }
+}
+
+/// Finds the first marker of `marker_len` distinct symbols, where two
+/// symbols for which `same` holds are not distinct. Positions are counted in
+/// whatever `symbols` yields, so `str::bytes` counts bytes and `str::chars`
+/// counts chars.
+fn find_marker_by<T, F>(
+    marker_len: usize,
+    symbols: impl IntoIterator<Item = T>,
+    same: F,
+) -> Option<usize>
+where
+    T: Copy,
+    F: Fn(T, T) -> bool,
+{
+    if marker_len == 0 {
+        return Some(0);
+    }
+    let mut window: VecDeque<T> = VecDeque::with_capacity(marker_len);
+    for (i, symbol) in symbols.into_iter().enumerate() {
+        // Without a lookup table we have to search the window itself, which
+        // is fine as markers are short
+        if let Some(j) = window.iter().rposition(|&seen| same(seen, symbol)) {
+            window.drain(..=j);
+        }
+        window.push_back(symbol);
+        if window.len() == marker_len {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+/// Watches a datastream one byte at a time for runs of `marker_len`
+/// distinct bytes.
+///
+/// Slides a window over the bytes while remembering where each byte was
+/// last seen, when a byte repeats inside the window the window start jumps
+/// past the earlier copy. Every byte is looked at once so this is O(n).
+struct MarkerDetector {
+    marker_len: usize,
+    // Position after the last occurrence of each byte, 0 for never seen
+    last_seen: [usize; 256],
+    window_start: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(marker_len: usize) -> Self {
+        Self {
+            marker_len,
+            last_seen: [0; 256],
+            window_start: 0,
+            position: 0,
+        }
+    }
+
+    /// Feeds the next byte of the datastream, returns the number of bytes
+    /// processed so far if that byte completed a marker.
+    fn push(&mut self, b: u8) -> Option<usize> {
+        self.position += 1;
+        let seen = &mut self.last_seen[b as usize];
+        self.window_start = self.window_start.max(*seen);
+        *seen = self.position;
+        if self.position - self.window_start == self.marker_len {
+            // Markers don't overlap, the next one has to be built from
+            // bytes received after this one
+            self.window_start = self.position;
+            return Some(self.position);
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marker {
+    kind: MarkerKind,
+    position: usize,
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            MarkerKind::StartOfPacket => write!(f, "start-of-packet after {}", self.position),
+            MarkerKind::StartOfMessage => write!(f, "start-of-message after {}", self.position),
+        }
+    }
+}
+
+/// Reports every start-of-packet and start-of-message marker in a stream of
+/// bytes as soon as the byte completing it arrives. Line endings are not
+/// part of the datastream and are skipped.
+pub struct Markers<I> {
+    bytes: I,
+    packets: MarkerDetector,
+    messages: MarkerDetector,
+    pending: Option<Marker>,
+}
+
+impl<I: Iterator<Item = u8>> Markers<I> {
+    pub fn new(bytes: I) -> Self {
+        Self {
+            bytes,
+            packets: MarkerDetector::new(4),
+            messages: MarkerDetector::new(14),
+            pending: None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Markers<I> {
+    type Item = Marker;
+
+    fn next(&mut self) -> Option<Marker> {
+        if let Some(marker) = self.pending.take() {
+            return Some(marker);
+        }
+        for b in self.bytes.by_ref() {
+            if b == b'\n' || b == b'\r' {
+                continue;
+            }
+            let packet = self.packets.push(b).map(|position| Marker {
+                kind: MarkerKind::StartOfPacket,
+                position,
+            });
+            let message = self.messages.push(b).map(|position| Marker {
+                kind: MarkerKind::StartOfMessage,
+                position,
+            });
+            match (packet, message) {
+                (Some(packet), message) => {
+                    self.pending = message;
+                    return Some(packet);
+                }
+                (None, Some(message)) => return Some(message),
+                (None, None) => (),
             }
         }
-        if dupes == false {
-            return i + marker_len;
+        None
+    }
+}
+
+/// A packet or message pulled out of the datastream. `offset` is where the
+/// payload starts, right after the marker that announced it.
+#[derive(Debug, PartialEq)]
+pub struct Frame<'a> {
+    offset: usize,
+    payload: &'a [u8],
+}
+
+/// Every packet and every message in a datastream.
+#[derive(Debug, PartialEq)]
+pub struct Transmission<'a> {
+    packets: Vec<Frame<'a>>,
+    messages: Vec<Frame<'a>>,
+}
+
+impl fmt::Display for Transmission<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, frames) in [("packet", &self.packets), ("message", &self.messages)] {
+            writeln!(f, "\t{} {name}s", frames.len())?;
+            for frame in frames {
+                writeln!(
+                    f,
+                    "\t\t{name} at {}: {}",
+                    frame.offset,
+                    String::from_utf8_lossy(frame.payload)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits the datastream on markers of `marker_len` distinct bytes, each
+/// payload runs until the next marker begins or the datastream ends.
+fn split_frames(input: &[u8], marker_len: usize) -> Vec<Frame<'_>> {
+    let mut detector = MarkerDetector::new(marker_len);
+    let mut frames = vec![];
+    let mut start = None;
+    for &b in input {
+        if let Some(end) = detector.push(b) {
+            if let Some(offset) = start {
+                frames.push(Frame {
+                    offset,
+                    payload: &input[offset..end - marker_len],
+                });
+            }
+            start = Some(end);
+        }
+    }
+    if let Some(offset) = start {
+        frames.push(Frame {
+            offset,
+            payload: &input[offset..],
+        });
+    }
+    frames
+}
+
+/// Decodes the whole datastream into packets, delimited by start-of-packet
+/// markers, and messages, delimited by start-of-message markers.
+pub fn decode(input: &str) -> Transmission<'_> {
+    let input = input.trim_end_matches(['\n', '\r']).as_bytes();
+    Transmission {
+        packets: split_frames(input, 4),
+        messages: split_frames(input, 14),
+    }
+}
+
+/// Streams markers out of any reader, stopping at the first read error.
+pub fn stream_markers<R: Read>(reader: R) -> io::Result<()> {
+    let mut error = None;
+    let bytes = BufReader::new(reader).bytes().map_while(|b| match b {
+        Ok(b) => Some(b),
+        Err(e) => {
+            error = Some(e);
+            None
         }
+    });
+    let mut stdout = io::stdout().lock();
+    for marker in Markers::new(bytes) {
+        writeln!(stdout, "{marker}")?;
+    }
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
     }
-    0
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::Lcg;
     #[test]
     fn example1_part_1_works() {
         //     bvwbjplbgvbhsrlpgdmjqwftvncz: first marker after character 5
-        assert_eq!(testable_exec_part1("bvwbjplbgvbhsrlpgdmjqwftvncz"), 5);
+        assert_eq!(testable_exec_part1("bvwbjplbgvbhsrlpgdmjqwftvncz"), Some(5));
     }
     #[test]
     fn example2_part_1_works() {
         //     nppdvjthqldpwncqszvftbrmjlhg: first marker after character 6
-        assert_eq!(testable_exec_part1("nppdvjthqldpwncqszvftbrmjlhg"), 6);
+        assert_eq!(testable_exec_part1("nppdvjthqldpwncqszvftbrmjlhg"), Some(6));
     }
     #[test]
     fn example3_part_1_works() {
         //     nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg: first marker after character 10
-        assert_eq!(testable_exec_part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 10);
+        assert_eq!(
+            testable_exec_part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"),
+            Some(10)
+        );
     }
     #[test]
     fn example4_part_1_works() {
         //     zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw: first marker after character 11
-        assert_eq!(testable_exec_part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 11);
+        assert_eq!(
+            testable_exec_part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"),
+            Some(11)
+        );
     }
 
     #[test]
     fn example1_part_2_works() {
         //mjqjpqmgbljsphdztnvjfqwrcgsmlb: first marker after character 19
-        assert_eq!(testable_exec_part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), 19);
+        assert_eq!(
+            testable_exec_part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
+            Some(19)
+        );
     }
     #[test]
     fn example2_part_2_works() {
         //bvwbjplbgvbhsrlpgdmjqwftvncz: first marker after character 23
-        assert_eq!(testable_exec_part2("bvwbjplbgvbhsrlpgdmjqwftvncz"), 23);
+        assert_eq!(
+            testable_exec_part2("bvwbjplbgvbhsrlpgdmjqwftvncz"),
+            Some(23)
+        );
     }
     #[test]
     fn example3_part_2_works() {
         //nppdvjthqldpwncqszvftbrmjlhg: first marker after character 23
-        assert_eq!(testable_exec_part2("nppdvjthqldpwncqszvftbrmjlhg"), 23);
+        assert_eq!(
+            testable_exec_part2("nppdvjthqldpwncqszvftbrmjlhg"),
+            Some(23)
+        );
     }
     #[test]
     fn example4_part_2_works() {
         //nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg: first marker after character 29
-        assert_eq!(testable_exec_part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
+        assert_eq!(
+            testable_exec_part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"),
+            Some(29)
+        );
     }
     #[test]
     fn example5_part_2_works() {
         //zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw: first marker after character 26
-        assert_eq!(testable_exec_part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
+        assert_eq!(
+            testable_exec_part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"),
+            Some(26)
+        );
+    }
+
+    #[test]
+    fn no_marker_is_none() {
+        assert_eq!(testable_exec_part1("abcabcabc"), None);
+        assert_eq!(testable_exec_part1(""), None);
+    }
+
+    #[test]
+    fn short_input_is_none() {
+        assert_eq!(testable_exec_part1("abc"), None);
+        assert_eq!(testable_exec_part2("abcdefghijklm"), None);
+    }
+
+    #[test]
+    fn marker_in_last_window_is_found() {
+        assert_eq!(testable_exec_part1("aaabcd"), Some(6));
+        assert_eq!(testable_exec_part1("abcd"), Some(4));
+    }
+
+    #[test]
+    fn trailing_newline_is_ignored() {
+        assert_eq!(testable_exec_part1("aabc\n"), None);
+        assert_eq!(testable_exec_part1("aabcd\r\n"), Some(5));
+    }
+
+    #[test]
+    fn markers_reports_every_marker() {
+        let markers: Vec<Marker> = Markers::new("aabcdabcdabcdefghijklmn\n".bytes()).collect();
+        let packet = |position| Marker {
+            kind: MarkerKind::StartOfPacket,
+            position,
+        };
+        assert_eq!(
+            markers,
+            vec![
+                packet(5),
+                packet(9),
+                packet(13),
+                packet(17),
+                packet(21),
+                Marker {
+                    kind: MarkerKind::StartOfMessage,
+                    position: 23
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn markers_first_of_each_kind_matches_puzzle() {
+        let stream = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let markers: Vec<Marker> = Markers::new(stream.bytes()).collect();
+        let first = |kind| markers.iter().find(|m| m.kind == kind).map(|m| m.position);
+        assert_eq!(first(MarkerKind::StartOfPacket), Some(7));
+        assert_eq!(first(MarkerKind::StartOfMessage), Some(19));
+    }
+
+    #[test]
+    fn markers_skip_line_endings() {
+        let markers: Vec<Marker> = Markers::new("ab\r\ncd".bytes()).collect();
+        assert_eq!(
+            markers,
+            vec![Marker {
+                kind: MarkerKind::StartOfPacket,
+                position: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn decode_splits_on_markers() {
+        let transmission = decode("aaabcdaaaabcdabcdbba\n");
+        assert_eq!(
+            transmission.packets,
+            vec![
+                Frame {
+                    offset: 6,
+                    payload: b"aaa"
+                },
+                Frame {
+                    offset: 13,
+                    payload: b""
+                },
+                Frame {
+                    offset: 17,
+                    payload: b"bba"
+                },
+            ]
+        );
+        assert!(transmission.messages.is_empty());
+    }
+
+    /// Builds a datastream out of `payloads`, each one announced by `marker`.
+    /// Payloads only use the first `marker.len() - 1` letters of the marker
+    /// and end on its first letter, so no marker can show up early.
+    fn encode(marker: &str, payloads: &[String]) -> String {
+        let mut stream = String::new();
+        for payload in payloads {
+            stream.push_str(marker);
+            stream.push_str(payload);
+        }
+        stream
+    }
+
+    fn generate_payloads(marker: &str, count: usize) -> Vec<String> {
+        let alphabet: Vec<char> = marker.chars().take(marker.len() - 1).collect();
+        let mut rng = Lcg::new(17);
+        (0..count)
+            .map(|_| {
+                let len = rng.below(40);
+                let mut payload: String = (0..len)
+                    .map(|_| alphabet[rng.below(alphabet.len())])
+                    .collect();
+                if !payload.is_empty() {
+                    payload.push(alphabet[0]);
+                }
+                payload
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_round_trips_generated_streams() {
+        for marker in ["abcd", "abcdefghijklmn"] {
+            let payloads = generate_payloads(marker, 200);
+            let stream = encode(marker, &payloads);
+            let transmission = decode(&stream);
+            let frames = match marker.len() {
+                4 => transmission.packets,
+                _ => transmission.messages,
+            };
+            let decoded: Vec<String> = frames
+                .iter()
+                .map(|frame| String::from_utf8(frame.payload.to_vec()).unwrap())
+                .collect();
+            assert_eq!(decoded, payloads);
+
+            let mut offset = 0;
+            for (frame, payload) in frames.iter().zip(&payloads) {
+                offset += marker.len();
+                assert_eq!(frame.offset, offset);
+                offset += payload.len();
+            }
+        }
+    }
+
+    #[test]
+    fn find_marker_by_agrees_with_bytes() {
+        for input in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        ] {
+            for marker_len in [4, 14] {
+                assert_eq!(
+                    find_marker_by(marker_len, input.bytes(), |a, b| a == b),
+                    find_start_of_markers(marker_len, input)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn find_marker_by_counts_chars() {
+        // é, ü and ö are two bytes each in UTF-8 sharing the same first byte,
+        // so there are never four distinct bytes in a row
+        let input = "aéaéüö";
+        assert_eq!(find_marker_by(4, input.chars(), |a, b| a == b), Some(6));
+        assert_eq!(find_marker_by(4, input.bytes(), |a, b| a == b), None);
+        assert_eq!(
+            find_marker_by(4, "日本語日本語".chars(), |a, b| a == b),
+            None
+        );
+        assert_eq!(
+            find_marker_by(3, "日本語日本語".chars(), |a, b| a == b),
+            Some(3)
+        );
+        assert_eq!(
+            find_marker_by(3, "日本語日本語".bytes(), |a, b| a == b),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn find_marker_by_ignores_case() {
+        let ignore_case = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+        assert_eq!(find_marker_by(4, "aAbBcdE".chars(), |a, b| a == b), Some(4));
+        assert_eq!(find_marker_by(4, "aAbBcdE".chars(), ignore_case), Some(7));
+        assert_eq!(find_marker_by(4, "σΣαβγ".chars(), ignore_case), Some(5));
+    }
+
+    /// Sorts and dedups every window, the way part 1 was first solved. Slow,
+    /// but obviously right.
+    fn find_start_of_markers_naive(marker_len: usize, input: &str) -> usize {
+        for i in 0..(input.len() - marker_len) {
+            let mut window: Vec<char> = input.chars().skip(i).take(marker_len).collect();
+            window.sort();
+            window.dedup();
+            if window.len() == marker_len {
+                return i + marker_len;
+            }
+        }
+        0
+    }
+
+    /// A stream of `len` repeated letters followed by a marker of
+    /// `marker_len` distinct letters, so the marker ends at `len + marker_len`.
+    fn generate_stream(len: usize, marker_len: usize) -> String {
+        let mut stream = "a".repeat(len);
+        stream.extend(('a'..='z').take(marker_len));
+        stream.push_str("aaa");
+        stream
+    }
+
+    #[test]
+    fn matches_naive_on_generated_stream() {
+        for marker_len in [4, 14] {
+            let stream = generate_stream(2000, marker_len);
+            assert_eq!(
+                find_start_of_markers(marker_len, &stream),
+                Some(find_start_of_markers_naive(marker_len, &stream))
+            );
+            assert_eq!(
+                find_start_of_markers(marker_len, &stream),
+                Some(2000 + marker_len)
+            );
+        }
+    }
+
+    /// The sliding window against the naive search on a 64 KiB stream, then
+    /// alone on 8 MiB, which the naive search is too slow for. Start it with
+    /// `cargo test --release -- --ignored --nocapture bench_find`.
+    #[test]
+    #[ignore]
+    fn bench_find_start_of_markers() {
+        use std::time::Instant;
+
+        let small = generate_stream(1 << 16, 14);
+        let now = Instant::now();
+        find_start_of_markers_naive(14, &small);
+        println!("naive, {} bytes: {:?}", small.len(), now.elapsed());
+        let now = Instant::now();
+        find_start_of_markers(14, &small);
+        println!("sliding window, {} bytes: {:?}", small.len(), now.elapsed());
+
+        let large = generate_stream(1 << 23, 14);
+        let now = Instant::now();
+        assert_eq!(find_start_of_markers(14, &large), Some((1 << 23) + 14));
+        println!("sliding window, {} bytes: {:?}", large.len(), now.elapsed());
     }
 }
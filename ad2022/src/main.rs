@@ -8,6 +8,10 @@ mod day7;
 mod day8;
 mod util;
 
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
 fn main() {
     let inputs = [
         "./src/day1_input",
@@ -23,12 +27,106 @@ fn main() {
     for (i, input) in inputs.iter().enumerate() {
         let input_string = util::load_input(input);
         match i {
-            0 => day1::exec(&input_string),
-            1 => day2::exec(&input_string),
-            2 => day3::exec(&input_string),
-            3 => day4::exec(&input_string),
-            4 => day5::exec(&input_string),
-            5 => day6::exec(&input_string),
+            0 => {
+                day1::exec(&input_string);
+                if util::has_flag("--day1-verbose") {
+                    day1::exec_report(&input_string);
+                }
+                if let Some(k) = util::flag_value("--day1-top") {
+                    let k = k.parse::<usize>().expect("Top k must be a number");
+                    if let Err(e) = day1::exec_top(io::stdin().lock(), k) {
+                        panic!("{e}");
+                    }
+                }
+            }
+            1 => {
+                day2::exec(&input_string);
+                if util::has_flag("--day2-explore") {
+                    day2::exec_explore(&input_string);
+                }
+                if let Some(format) = util::flag_value("--day2-tournament") {
+                    let format = format.parse().unwrap_or_else(|e| panic!("{e}"));
+                    let guides: Vec<(String, String)> = util::flag_values("--day2-player")
+                        .into_iter()
+                        .map(|path| {
+                            let name = Path::new(&path)
+                                .file_stem()
+                                .map_or(path.clone(), |s| s.to_string_lossy().into_owned());
+                            (name, util::load_input(&path))
+                        })
+                        .collect();
+                    day2::exec_tournament(&input_string, &guides, format);
+                }
+            }
+            2 => {
+                day3::exec(&input_string);
+                if util::has_flag("--day3-plan") {
+                    day3::exec_plan(&input_string);
+                }
+                let compartments = util::flag_value("--day3-compartments");
+                let group_size = util::flag_value("--day3-group-size");
+                if compartments.is_some() || group_size.is_some() {
+                    let parse = |n: Option<String>, default| match n {
+                        Some(n) => n.parse::<usize>().expect("Expected a number"),
+                        None => default,
+                    };
+                    day3::exec_with(&input_string, parse(compartments, 2), parse(group_size, 3));
+                }
+            }
+            3 => {
+                day4::exec(&input_string);
+                if let Some(more_than) = util::flag_value("--day4-coverage") {
+                    let more_than = more_than.parse::<usize>().expect("Expected a number");
+                    day4::exec_coverage(&input_string, more_than);
+                }
+                if util::has_flag("--day4-draw") {
+                    let width = match util::flag_value("--day4-draw-width") {
+                        Some(width) => width.parse::<usize>().expect("Expected a number"),
+                        None => 80,
+                    };
+                    day4::exec_draw(&input_string, width);
+                }
+            }
+            4 => {
+                day5::exec(&input_string);
+                let crane = match util::flag_value("--day5-crane") {
+                    Some(model) => model.parse().unwrap_or_else(|e| panic!("{e}")),
+                    None => day5::Crane::CrateMover9000,
+                };
+                if let Some(path) = util::flag_value("--day5-frames") {
+                    day5::write_frames(&input_string, crane, &path)
+                        .expect("Could not write day 5 frames");
+                }
+                if let Some(path) = util::flag_value("--day5-reverse") {
+                    let final_layout = util::load_input(&path);
+                    match day5::reverse_procedure(&input_string, &final_layout, crane) {
+                        Ok(start) => println!("\tstarting stacks:\n{start}"),
+                        Err(e) => println!("\tstarting stacks: {e}"),
+                    }
+                }
+                if let Some(delay) = util::flag_value("--day5-animate") {
+                    let delay = delay.parse::<u64>().expect("Frame delay must be in ms");
+                    day5::animate(&input_string, crane, Duration::from_millis(delay));
+                }
+            }
+            5 => {
+                day6::exec(&input_string);
+                let unit = util::flag_value("--day6-unit");
+                let ignore_case = util::has_flag("--day6-ignore-case");
+                if unit.is_some() || ignore_case {
+                    let unit = match unit {
+                        Some(unit) => unit.parse().unwrap_or_else(|e| panic!("{e}")),
+                        None => day6::Unit::Bytes,
+                    };
+                    day6::exec_with(&input_string, unit, ignore_case);
+                }
+                if util::has_flag("--day6-decode") {
+                    print!("{}", day6::decode(&input_string));
+                }
+                if util::has_flag("--day6-stream") {
+                    day6::stream_markers(io::stdin().lock()).expect("Could not read stdin");
+                }
+            }
             6 => (), //day7::exec(&input_string),
             7 => day8::exec(&input_string),
             _ => panic!("Day: {} not implemented yet!", i + 1),
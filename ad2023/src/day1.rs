@@ -1,6 +1,6 @@
-use std::cmp::Ord;
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 
 pub fn exec(input: &str) {
     let res1 = testable_exec_part1(input);
@@ -9,6 +9,13 @@ pub fn exec(input: &str) {
     println!("Day 1\n\tpart 1: {res1}\n\tpart 2: {res2}");
 }
 
+pub fn exec_with(input: &str, vocabulary: &Vocabulary) {
+    let res1 = sum_lines(input, &DigitScanner::new(&vocabulary.numerals()));
+    let res2 = sum_lines(input, &DigitScanner::new(&vocabulary.patterns()));
+
+    println!("Day 1 ({vocabulary})\n\tpart 1: {res1}\n\tpart 2: {res2}");
+}
+
 ///--- Day 1: Trebuchet?! ---
 ///
 ///Something is wrong with global snow production, and you've been selected to take a look. The Elves have even given you a map; on it, they've used stars to mark the top fifty locations that are likely to be having problems.
@@ -34,7 +41,8 @@ pub fn exec(input: &str) {
 ///
 ///Consider your entire calibration document. What is the sum of all of the calibration values?
 fn testable_exec_part1(input: &str) -> usize {
-    sum_lines(input, true)
+    let vocabulary = Vocabulary::new(Language::English, false);
+    sum_lines(input, &DigitScanner::new(&vocabulary.numerals()))
 }
 
 /// --- Part Two ---
@@ -55,83 +63,324 @@ fn testable_exec_part1(input: &str) -> usize {
 ///
 /// In this example, the calibration values are 29, 83, 13, 24, 42, 14, and 76. Adding these together produces 281.
 fn testable_exec_part2(input: &str) -> usize {
-    sum_lines(input, false)
+    let vocabulary = Vocabulary::new(Language::English, false);
+    sum_lines(input, &DigitScanner::new(&vocabulary.patterns()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    German,
+    French,
+}
+
+impl Language {
+    /// Number words for 0 to 9, indexed by their value.
+    fn words(&self) -> [&'static str; 10] {
+        match self {
+            Language::English => [
+                "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+            ],
+            Language::German => [
+                "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+            ],
+            Language::French => [
+                "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+            ],
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "english" | "en" => Ok(Language::English),
+            "german" | "de" => Ok(Language::German),
+            "french" | "fr" => Ok(Language::French),
+            _ => Err(format!("Unknown language: {s}")),
+        }
+    }
+}
+
+/// The words a calibration document may spell its digits with. Zero, as a
+/// numeral and as a word, only counts when asked for.
+#[derive(Debug)]
+pub struct Vocabulary {
+    language: Language,
+    zero: bool,
+    words: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    pub fn new(language: Language, zero: bool) -> Vocabulary {
+        let words = language
+            .words()
+            .iter()
+            .zip(0..)
+            .skip(if zero { 0 } else { 1 })
+            .map(|(word, value)| (word.to_string(), value))
+            .collect();
+        Vocabulary {
+            language,
+            zero,
+            words,
+        }
+    }
+
+    /// Adds words from a list with one `word value` or `word=value` per
+    /// line, replacing any word that is already known.
+    pub fn with_word_list(mut self, list: &str) -> Result<Vocabulary, String> {
+        for (i, line) in list.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((word, value)) = line
+                .split_once('=')
+                .or_else(|| line.split_once(char::is_whitespace))
+            else {
+                return Err(format!("line {}: expected `word value`", i + 1));
+            };
+            let (word, value) = (word.trim(), value.trim());
+            let value = match value.parse::<u32>() {
+                Ok(value) if value <= 9 => value,
+                _ => return Err(format!("line {}: {value} is not a digit", i + 1)),
+            };
+            if word.is_empty() {
+                return Err(format!("line {}: missing word", i + 1));
+            }
+            self.words.retain(|(known, _)| known != word);
+            self.words.push((word.to_string(), value));
+        }
+        Ok(self)
+    }
+
+    fn numerals(&self) -> Vec<(String, u32)> {
+        let first = if self.zero { 0 } else { 1 };
+        (first..=9)
+            .map(|value| (value.to_string(), value))
+            .collect()
+    }
+
+    fn patterns(&self) -> Vec<(String, u32)> {
+        let mut patterns = self.numerals();
+        patterns.extend(self.words.iter().cloned());
+        patterns
+    }
+}
+
+impl fmt::Display for Vocabulary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}, {} words", self.language, self.words.len())?;
+        if self.zero {
+            write!(f, ", with zero")?;
+        }
+        Ok(())
+    }
+}
+
+fn sum_lines(input: &str, scanner: &DigitScanner) -> usize {
+    input
+        .lines()
+        .filter_map(|line| scanner.first_and_last(line))
+        .map(|(first, last)| (first * 10 + last) as usize)
+        .sum()
 }
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
-struct OffsetDigitPair {
+/// A digit found on a calibration line, `offset` is in bytes.
+#[derive(Debug, PartialEq)]
+struct Token {
     offset: usize,
-    digit: String,
+    text: String,
+    value: u32,
+}
+
+/// What the decoder made of one line: every digit it could see and the
+/// calibration value taken from the first and last of them.
+#[derive(Debug, PartialEq)]
+struct LineReport {
+    line: usize,
+    tokens: Vec<Token>,
+    value: Option<usize>,
 }
 
-fn sum_lines(input: &str, only_num_digit: bool) -> usize {
-    let line_results = input
+impl fmt::Display for LineReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "\tline {}: {value:>2} from", self.line)?,
+            None => return write!(f, "\tline {}: warning, no digits found", self.line),
+        }
+        for token in &self.tokens {
+            write!(f, " {}@{}", token.text, token.offset)?;
+        }
+        Ok(())
+    }
+}
+
+fn diagnose(input: &str, scanner: &DigitScanner) -> Vec<LineReport> {
+    input
         .lines()
-        .map(|line| extract_first_and_last_digits(line, only_num_digit))
-        .collect::<Vec<Option<(String, String)>>>();
-
-    let sum = line_results
-        .iter()
-        .filter_map(|pos| match pos {
-            Some((first, last)) => match format!("{first}{last}").parse::<usize>() {
-                Ok(parsed_number) => return Some(parsed_number),
-                Err(_) => panic!("Could not parse number from: {first} {last}"),
-            },
-            None => return None,
+        .enumerate()
+        .map(|(i, line)| {
+            let tokens = scanner
+                .forward
+                .matches(line.bytes())
+                .into_iter()
+                .map(|(offset, len, value)| Token {
+                    offset,
+                    text: line[offset..offset + len].to_string(),
+                    value,
+                })
+                .collect();
+            let value = scanner
+                .first_and_last(line)
+                .map(|(first, last)| (first * 10 + last) as usize);
+            LineReport {
+                line: i + 1,
+                tokens,
+                value,
+            }
         })
-        .sum::<usize>();
-    sum
-}
-
-fn extract_first_and_last_digits(input: &str, only_num_digit: bool) -> Option<(String, String)> {
-    let text_digits = vec![
-        ("one", "1"),
-        ("two", "2"),
-        ("three", "3"),
-        ("four", "4"),
-        ("five", "5"),
-        ("six", "6"),
-        ("seven", "7"),
-        ("eight", "8"),
-        ("nine", "9"),
-    ];
-    let mut first_heap = BinaryHeap::new();
-    let mut last_heap = BinaryHeap::new();
-
-    for (text_digit, num_digit) in text_digits {
-        // Find text digits
-        if !only_num_digit {
-            input.match_indices(text_digit).for_each(|digit| {
-                last_heap.push(OffsetDigitPair {
-                    offset: digit.0,
-                    digit: num_digit.to_string(),
-                });
-                first_heap.push(Reverse(OffsetDigitPair {
-                    offset: digit.0,
-                    digit: num_digit.to_string(),
-                }));
-            });
+        .collect()
+}
+
+pub fn exec_report(input: &str, vocabulary: &Vocabulary) {
+    let reports = diagnose(input, &DigitScanner::new(&vocabulary.patterns()));
+    println!("Day 1 report ({vocabulary})");
+    for report in &reports {
+        println!("{report}");
+    }
+    let total: usize = reports.iter().filter_map(|r| r.value).sum();
+    let skipped = reports.iter().filter(|r| r.value.is_none()).count();
+    println!(
+        "\ttotal: {total} from {} lines, {skipped} without digits",
+        reports.len() - skipped
+    );
+}
+
+/// Aho-Corasick automaton with the failure links folded into a full
+/// transition table, so each byte is a single lookup.
+struct Automaton {
+    next: Vec<[usize; 256]>,
+    /// Length and value of every pattern ending in this state.
+    outputs: Vec<Vec<(usize, u32)>>,
+    longest: usize,
+}
+
+impl Automaton {
+    fn new(patterns: impl Iterator<Item = (Vec<u8>, u32)>) -> Automaton {
+        let mut next = vec![[0; 256]];
+        let mut outputs = vec![vec![]];
+        let mut longest = 0;
+        for (pattern, value) in patterns {
+            let mut state = 0;
+            for &b in &pattern {
+                if next[state][b as usize] == 0 {
+                    next.push([0; 256]);
+                    outputs.push(vec![]);
+                    next[state][b as usize] = next.len() - 1;
+                }
+                state = next[state][b as usize];
+            }
+            outputs[state].push((pattern.len(), value));
+            longest = longest.max(pattern.len());
+        }
+
+        // Breadth first, so the state a failure link points to is always
+        // complete before it is needed.
+        let mut fail = vec![0; next.len()];
+        let mut queue: VecDeque<usize> = next[0].iter().copied().filter(|&s| s != 0).collect();
+        while let Some(state) = queue.pop_front() {
+            let fallback = next[fail[state]];
+            for (b, child) in next[state].iter_mut().enumerate() {
+                if *child == 0 {
+                    *child = fallback[b];
+                } else {
+                    fail[*child] = fallback[b];
+                    let inherited = outputs[fallback[b]].clone();
+                    outputs[*child].extend(inherited);
+                    queue.push_back(*child);
+                }
+            }
+        }
+
+        Automaton {
+            next,
+            outputs,
+            longest,
+        }
+    }
+
+    /// Every match in `bytes` as start, length and value, in order of start.
+    fn matches(&self, bytes: impl Iterator<Item = u8>) -> Vec<(usize, usize, u32)> {
+        let mut state = 0;
+        let mut matches = vec![];
+        for (i, b) in bytes.enumerate() {
+            state = self.next[state][b as usize];
+            for &(len, value) in &self.outputs[state] {
+                matches.push((i + 1 - len, len, value));
+            }
         }
+        matches.sort_unstable();
+        matches
+    }
 
-        // Find numerical digits
-        input.match_indices(num_digit).for_each(|digit| {
-            last_heap.push(OffsetDigitPair {
-                offset: digit.0,
-                digit: num_digit.to_string(),
-            });
-            first_heap.push(Reverse(OffsetDigitPair {
-                offset: digit.0,
-                digit: num_digit.to_string(),
-            }));
-        });
+    /// Value of the match starting earliest in `bytes`, the longest if
+    /// several start together. Stops as soon as no later match could start
+    /// before the best one found so far.
+    fn earliest(&self, bytes: impl Iterator<Item = u8>) -> Option<u32> {
+        let mut state = 0;
+        let mut best: Option<(usize, usize, u32)> = None;
+        for (i, b) in bytes.enumerate() {
+            state = self.next[state][b as usize];
+            for &(len, value) in &self.outputs[state] {
+                let start = i + 1 - len;
+                if best.is_none_or(|(best_start, best_len, _)| {
+                    start < best_start || (start == best_start && len > best_len)
+                }) {
+                    best = Some((start, len, value));
+                }
+            }
+            if let Some((best_start, _, _)) = best {
+                if i + 1 >= best_start + self.longest {
+                    break;
+                }
+            }
+        }
+        best.map(|(_, _, value)| value)
     }
+}
+
+/// Finds the first and last digit of a line in one pass from each end, the
+/// backward automaton is built from the reversed patterns. Where words
+/// overlap, like `eightwo`, the first digit is the word starting first and
+/// the last digit the word ending last, the longer word winning a tie.
+struct DigitScanner {
+    forward: Automaton,
+    backward: Automaton,
+}
 
-    if let Some(first_digit) = first_heap.peek() {
-        if let Some(last_digit) = last_heap.peek() {
-            return Some((first_digit.0.digit.clone(), last_digit.digit.clone()));
+impl DigitScanner {
+    fn new<S: AsRef<str>>(patterns: &[(S, u32)]) -> DigitScanner {
+        let forward = patterns
+            .iter()
+            .map(|(p, v)| (p.as_ref().bytes().collect(), *v));
+        let backward = patterns
+            .iter()
+            .map(|(p, v)| (p.as_ref().bytes().rev().collect(), *v));
+        DigitScanner {
+            forward: Automaton::new(forward),
+            backward: Automaton::new(backward),
         }
     }
-    None
+
+    fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let first = self.forward.earliest(line.bytes())?;
+        let last = self.backward.earliest(line.bytes().rev())?;
+        Some((first, last))
+    }
 }
 
 #[test]
@@ -181,3 +430,123 @@ fn part2_test_samples_from_real_input() {
     3fvsghvkqkbfivenine";
     assert_eq!(testable_exec_part2(example), 1263);
 }
+
+#[test]
+fn scanner_handles_overlapping_words() {
+    let scanner = DigitScanner::new(&Vocabulary::new(Language::English, false).patterns());
+    assert_eq!(scanner.first_and_last("eightwo"), Some((8, 2)));
+    assert_eq!(scanner.first_and_last("xtwone"), Some((2, 1)));
+    assert_eq!(scanner.first_and_last("sevenine"), Some((7, 9)));
+    assert_eq!(scanner.first_and_last("3"), Some((3, 3)));
+    assert_eq!(scanner.first_and_last("thre"), None);
+    assert_eq!(scanner.first_and_last(""), None);
+}
+
+#[test]
+fn scanner_prefers_earliest_start() {
+    // "bc" ends first but "abcd" starts first.
+    let scanner = DigitScanner::new(&[("abcd", 1), ("bc", 2), ("d", 3)]);
+    assert_eq!(scanner.first_and_last("xabcdx"), Some((1, 1)));
+    assert_eq!(scanner.first_and_last("xabcx"), Some((2, 2)));
+    assert_eq!(scanner.first_and_last("xabcxd"), Some((2, 3)));
+
+    // Same start or same end, the longer word wins from either side.
+    let scanner = DigitScanner::new(&[("six", 6), ("sixty", 7), ("ty", 2)]);
+    assert_eq!(scanner.first_and_last("sixty"), Some((7, 7)));
+}
+
+#[test]
+fn vocabularies() {
+    let german = Vocabulary::new(Language::German, false);
+    assert_eq!(
+        sum_lines(
+            "fünfzehn\nachtzig2\nnullneun",
+            &DigitScanner::new(&german.patterns())
+        ),
+        55 + 82 + 99
+    );
+
+    let french = Vocabulary::new(Language::French, true);
+    assert_eq!(
+        sum_lines("zérodeux\n0huit", &DigitScanner::new(&french.patterns())),
+        2 + 8
+    );
+    assert_eq!(
+        sum_lines("zérodeux\n0huit", &DigitScanner::new(&french.numerals())),
+        0
+    );
+
+    let custom = Vocabulary::new(Language::English, false)
+        .with_word_list("# Swedish\nett 1\ntvå=2\n\none=7")
+        .unwrap();
+    let scanner = DigitScanner::new(&custom.patterns());
+    assert_eq!(scanner.first_and_last("etttvå"), Some((1, 2)));
+    assert_eq!(scanner.first_and_last("onethree"), Some((7, 3)));
+
+    let english = || Vocabulary::new(Language::English, false);
+    assert!(english().with_word_list("ett").is_err());
+    assert!(english().with_word_list("ett 10").is_err());
+    assert!(english().with_word_list("=1").is_err());
+    assert_eq!("FR".parse(), Ok(Language::French));
+    assert!("klingon".parse::<Language>().is_err());
+}
+
+#[test]
+fn scanner_matches_brute_force() {
+    let patterns = Vocabulary::new(Language::English, false).patterns();
+    let scanner = DigitScanner::new(&patterns);
+    let brute_force = |line: &str| {
+        let digits: Vec<u32> = (0..line.len())
+            .filter_map(|i| {
+                patterns
+                    .iter()
+                    .find(|(p, _)| line[i..].starts_with(p))
+                    .map(|(_, v)| *v)
+            })
+            .collect();
+        Some((*digits.first()?, *digits.last()?))
+    };
+
+    let alphabet = b"onetwhrfuivsxg19";
+    let mut rng = crate::util::Lcg::new(5);
+    for _ in 0..2000 {
+        let line: String = (0..12)
+            .map(|_| alphabet[rng.below(alphabet.len())] as char)
+            .collect();
+        assert_eq!(scanner.first_and_last(&line), brute_force(&line), "{line}");
+    }
+}
+
+#[test]
+fn report_lists_tokens_and_missing_digits() {
+    let scanner = DigitScanner::new(&Vocabulary::new(Language::English, false).patterns());
+    let reports = diagnose("sgeightwo3\nnothing here\n7", &scanner);
+    assert_eq!(
+        reports[0].tokens,
+        vec![
+            Token {
+                offset: 2,
+                text: "eight".to_string(),
+                value: 8
+            },
+            Token {
+                offset: 6,
+                text: "two".to_string(),
+                value: 2
+            },
+            Token {
+                offset: 9,
+                text: "3".to_string(),
+                value: 3
+            },
+        ]
+    );
+    assert_eq!(reports[0].value, Some(83));
+    assert_eq!(
+        reports[0].to_string(),
+        "\tline 1: 83 from eight@2 two@6 3@9"
+    );
+    assert_eq!(reports[1].value, None);
+    assert_eq!(reports[1].to_string(), "\tline 2: warning, no digits found");
+    assert_eq!(reports[2].to_string(), "\tline 3: 77 from 7@0");
+}
@@ -1,4 +1,4 @@
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeMap, fmt, ops::Range, str::FromStr};
 
 pub fn exec(input: &str) {
     let res1 = testable_exec_part1(input);
@@ -6,6 +6,15 @@ pub fn exec(input: &str) {
 
     println!("Day 2\n\tpart 1: {res1}\n\tpart 2: {res2}");
 }
+
+pub fn exec_with_bag(input: &str, bag: &str) {
+    let bag = CubeSet::from_str(bag).unwrap_or_else(|e| panic!("{}", e.pretty(bag)));
+    let res1 = possible_games(input, &bag);
+    let colors: Vec<&str> = bag.cubes.keys().map(String::as_str).collect();
+    let res2 = total_power(input, &colors);
+
+    println!("Day 2 (bag of {bag})\n\tpossible games: {res1}\n\tpower: {res2}");
+}
 /// --- Day 2: Cube Conundrum ---
 ///
 /// You're launched high into the atmosphere! The apex of your trajectory just barely reaches the surface of a large island floating in the sky.
@@ -46,17 +55,17 @@ pub fn exec(input: &str) {
 /// Determine which games would have been possible if the bag had been loaded with only 12 red cubes, 13 green cubes, and 14 blue cubes.
 /// What is the sum of the IDs of those games?
 fn testable_exec_part1(input: &str) -> usize {
-    let (red, green, blue) = (12, 13, 14);
-    let sum = input
-        .lines()
-        .map(|line| match Game::from_str(line) {
-            Ok(game) => game,
-            Err(e) => panic!("{e:?}"),
-        })
-        .filter(|game| game.possible(red, green, blue))
+    let bag = CubeSet::from_iter([("red", 12), ("green", 13), ("blue", 14)]);
+    possible_games(input, &bag)
+}
+
+/// Sum of the IDs of the games that could have been played with `bag`.
+fn possible_games(input: &str, bag: &CubeSet) -> usize {
+    parse_games(input)
+        .into_iter()
+        .filter(|game| game.possible(bag))
         .map(|game| game.id)
-        .sum::<usize>();
-    sum
+        .sum::<usize>()
 }
 
 /// --- Part Two ---
@@ -88,14 +97,25 @@ fn testable_exec_part1(input: &str) -> usize {
 ///
 /// For each game, find the minimum set of cubes that must have been present. What is the sum of the power of these sets?
 fn testable_exec_part2(input: &str) -> usize {
-    let sum = input
+    total_power(input, &["red", "green", "blue"])
+}
+
+fn total_power(input: &str, colors: &[&str]) -> usize {
+    parse_games(input)
+        .iter()
+        .map(|game| game.power(colors))
+        .sum::<usize>()
+}
+
+fn parse_games(input: &str) -> Vec<Game> {
+    input
         .lines()
-        .map(|line| match Game::from_str(line) {
-            Ok(game) => game.power(),
-            Err(e) => panic!("{e:?}"),
+        .enumerate()
+        .map(|(i, line)| match Game::from_str(line) {
+            Ok(game) => game,
+            Err(e) => panic!("{}", e.on_line(i + 1).pretty(line)),
         })
-        .sum::<usize>();
-    sum
+        .collect()
 }
 
 #[derive(Debug, PartialEq)]
@@ -104,25 +124,128 @@ struct Game {
     cubesets: Vec<CubeSet>,
 }
 
+/// What the parser was looking for when it gave up.
 #[derive(Debug, PartialEq, Eq)]
-struct ParseGameError;
+enum Expected {
+    Game,
+    GameId,
+    Colon,
+    Number,
+    SmallerNumber,
+    Space,
+    Color,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expected = match self {
+            Expected::Game => "`Game` followed by an ID",
+            Expected::GameId => "game ID after `Game`",
+            Expected::Colon => "`:` after game ID",
+            Expected::Number => "number before colour",
+            Expected::SmallerNumber => "a smaller number of cubes",
+            Expected::Space => "space between number and colour",
+            Expected::Color => "colour after number",
+        };
+        write!(f, "{expected}")
+    }
+}
+
+/// A parse error pointing at the bytes of the line it couldn't make sense
+/// of. `line` starts at 1 and is only known to callers parsing a whole
+/// record, `column` counts chars so it lines up with what is printed.
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError {
+    line: Option<usize>,
+    column: usize,
+    span: Range<usize>,
+    expected: Expected,
+}
+
+impl ParseError {
+    fn new(source: &str, span: Range<usize>, expected: Expected) -> Self {
+        Self {
+            line: None,
+            column: source[..span.start].chars().count() + 1,
+            span,
+            expected,
+        }
+    }
+
+    fn on_line(self, line: usize) -> Self {
+        Self {
+            line: Some(line),
+            ..self
+        }
+    }
+
+    /// Shows the error under the offending text, rustc style:
+    ///
+    /// error: expected number before colour
+    ///  --> line 3, column 18
+    ///   |
+    /// 3 | Game 3: 8 green, x blue
+    ///   |                  ^
+    ///
+    /// Without a line number the gutter is left blank.
+    fn pretty(&self, source: &str) -> String {
+        let carets = source[self.span.clone()].chars().count().max(1);
+        let (location, line) = match self.line {
+            Some(line) => (format!("line {line}, column"), line.to_string()),
+            None => ("column".to_string(), String::new()),
+        };
+        let gutter = " ".repeat(line.len().max(1));
+        let line = format!("{line:>width$}", width = gutter.len());
+        format!(
+            "error: expected {}\n{gutter}--> {location} {}\n{gutter} |\n{line} | {source}\n{gutter} | {}{}",
+            self.expected,
+            self.column,
+            " ".repeat(self.column - 1),
+            "^".repeat(carets)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {} at ", self.expected)?;
+        if let Some(line) = self.line {
+            write!(f, "line {line}, ")?;
+        }
+        write!(f, "column {}", self.column)
+    }
+}
 
 impl FromStr for Game {
-    type Err = ParseGameError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let line: Vec<&str> = s.split(":").collect();
-        if line.len() != 2 {
-            return Err(ParseGameError);
-        }
-        let id = line[0]
-            .replace("Game ", "")
+        let first_word = s.find(char::is_whitespace).unwrap_or(s.len());
+        let Some(rest) = s.strip_prefix("Game ") else {
+            return Err(ParseError::new(s, 0..first_word, Expected::Game));
+        };
+        let id_start = s.len() - rest.len();
+        let id_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let id = rest[..id_len]
             .parse::<usize>()
-            .map_err(|_| ParseGameError)?;
-        let cubesets: Vec<CubeSet> = line[1]
-            .split(";")
-            .map(|input| CubeSet::from_str(input).expect("Could not parse cube set from string"))
-            .collect();
+            .map_err(|_| ParseError::new(s, id_start..id_start + id_len, Expected::GameId))?;
+        let colon = id_start + id_len;
+        if !s[colon..].starts_with(':') {
+            let end = s[colon..]
+                .chars()
+                .next()
+                .map_or(colon, |c| colon + c.len_utf8());
+            return Err(ParseError::new(s, colon..end, Expected::Colon));
+        }
+
+        let mut cubesets = vec![];
+        let mut offset = colon + 1;
+        for set in s[offset..].split(';') {
+            cubesets.push(parse_cubeset(s, offset..offset + set.len())?);
+            offset += set.len() + 1;
+        }
         Ok(Game::new(id, cubesets))
     }
 }
@@ -132,81 +255,122 @@ impl Game {
         Self { id, cubesets }
     }
 
-    fn possible(&self, red: usize, green: usize, blue: usize) -> bool {
-        let max = self.max_colors();
-        max.red <= red && max.green <= green && max.blue <= blue
+    /// A colour the bag has no cubes of can't be shown at all.
+    fn possible(&self, bag: &CubeSet) -> bool {
+        self.max_colors()
+            .cubes
+            .iter()
+            .all(|(color, &count)| count <= bag.get(color))
     }
 
     fn max_colors(&self) -> CubeSet {
-        let mut cubeset = CubeSet::new(0, 0, 0);
+        let mut cubeset = CubeSet::default();
         self.cubesets.iter().for_each(|set| {
-            cubeset.red = cubeset.red.max(set.red);
-            cubeset.green = cubeset.green.max(set.green);
-            cubeset.blue = cubeset.blue.max(set.blue);
+            for (color, &count) in &set.cubes {
+                let max = cubeset.cubes.entry(color.clone()).or_insert(0);
+                *max = (*max).max(count);
+            }
         });
         cubeset
     }
 
-    fn power(&self) -> usize {
+    /// Product over `colors`, a colour the game never shows needs 0 cubes.
+    fn power(&self, colors: &[&str]) -> usize {
         let max = self.max_colors();
-        max.red * max.green * max.blue
+        colors.iter().map(|color| max.get(color)).product()
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// Cube counts by colour, any colour name is allowed.
+#[derive(Debug, Default, PartialEq)]
 struct CubeSet {
-    red: usize,
-    green: usize,
-    blue: usize,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-enum ParseCubeSetError {
-    RedFail,
-    GreenFail,
-    BlueFail,
-    SplitFail,
-    NonColorFail,
+    cubes: BTreeMap<String, usize>,
 }
 
 impl FromStr for CubeSet {
-    type Err = ParseCubeSetError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let comma_sep: Vec<&str> = s.split(",").collect();
-        let mut r = 0;
-        let mut g = 0;
-        let mut b = 0;
-        for c in comma_sep {
-            match c.trim().split_once(" ") {
-                Some((number, color)) => match color {
-                    "red" => {
-                        r = number
-                            .parse::<usize>()
-                            .map_err(|_| ParseCubeSetError::RedFail)?
-                    }
-                    "green" => {
-                        g = number
-                            .parse::<usize>()
-                            .map_err(|_| ParseCubeSetError::GreenFail)?
-                    }
-                    "blue" => {
-                        b = number
-                            .parse::<usize>()
-                            .map_err(|_| ParseCubeSetError::BlueFail)?
-                    }
-                    _ => return Err(ParseCubeSetError::NonColorFail),
-                },
-                None => return Err(ParseCubeSetError::SplitFail),
-            }
+        parse_cubeset(s, 0..s.len())
+    }
+}
+
+/// Parses the cube set at `set` in `line`, so spans in errors are relative
+/// to the whole line.
+fn parse_cubeset(line: &str, set: Range<usize>) -> Result<CubeSet, ParseError> {
+    let mut cubeset = CubeSet::default();
+    let mut item_start = set.start;
+    for item in line[set].split(",") {
+        let trimmed = item.trim_start();
+        let at = item_start + item.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        item_start += item.len() + 1;
+
+        let number_len = trimmed
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(trimmed.len());
+        if number_len == 0 {
+            let word = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+            return Err(ParseError::new(line, at..at + word, Expected::Number));
+        }
+        let number = trimmed[..number_len]
+            .parse::<usize>()
+            .map_err(|_| ParseError::new(line, at..at + number_len, Expected::SmallerNumber))?;
+
+        let after_number = at + number_len;
+        let rest = &trimmed[number_len..];
+        let color = rest.trim_start();
+        if color.is_empty() {
+            return Err(ParseError::new(
+                line,
+                after_number..after_number,
+                Expected::Color,
+            ));
+        }
+        if color.len() == rest.len() {
+            return Err(ParseError::new(
+                line,
+                after_number..after_number,
+                Expected::Space,
+            ));
+        }
+        let color_start = after_number + rest.len() - color.len();
+        if !color
+            .chars()
+            .all(|c| c.is_alphabetic() || c == ' ' || c == '-')
+        {
+            let span = color_start..color_start + color.len();
+            return Err(ParseError::new(line, span, Expected::Color));
         }
-        Ok(CubeSet::new(r, g, b))
+        *cubeset.cubes.entry(color.to_string()).or_insert(0) += number;
+    }
+    Ok(cubeset)
+}
+
+impl<'a> FromIterator<(&'a str, usize)> for CubeSet {
+    fn from_iter<I: IntoIterator<Item = (&'a str, usize)>>(iter: I) -> Self {
+        let cubes = iter
+            .into_iter()
+            .map(|(color, count)| (color.to_string(), count))
+            .collect();
+        Self { cubes }
+    }
+}
+
+impl fmt::Display for CubeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cubes: Vec<String> = self
+            .cubes
+            .iter()
+            .map(|(color, count)| format!("{count} {color}"))
+            .collect();
+        write!(f, "{}", cubes.join(", "))
     }
 }
 
 impl CubeSet {
-    fn new(red: usize, green: usize, blue: usize) -> Self {
-        Self { red, green, blue }
+    fn get(&self, color: &str) -> usize {
+        self.cubes.get(color).copied().unwrap_or(0)
     }
 }
 
@@ -214,11 +378,7 @@ impl CubeSet {
 fn cubeset_from_str_works() {
     let example = "3 blue, 4 red, 1 green";
     assert_eq!(
-        CubeSet {
-            red: 4,
-            green: 1,
-            blue: 3
-        },
+        CubeSet::from_iter([("red", 4), ("green", 1), ("blue", 3)]),
         CubeSet::from_str(example).unwrap()
     );
 }
@@ -229,21 +389,9 @@ fn game_from_str_works() {
         Game {
             id: 1,
             cubesets: vec![
-                CubeSet {
-                    red: 4,
-                    blue: 3,
-                    green: 0
-                },
-                CubeSet {
-                    red: 1,
-                    green: 2,
-                    blue: 6
-                },
-                CubeSet {
-                    red: 0,
-                    green: 2,
-                    blue: 0
-                }
+                CubeSet::from_iter([("red", 4), ("blue", 3)]),
+                CubeSet::from_iter([("red", 1), ("green", 2), ("blue", 6)]),
+                CubeSet::from_iter([("green", 2)]),
             ]
         },
         Game::from_str(example).unwrap()
@@ -254,8 +402,10 @@ fn game_from_str_works() {
 fn game_possibility_works() {
     let example = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
     let game = Game::from_str(example).unwrap();
-    assert!(game.possible(10, 10, 10));
-    assert!(!game.possible(1, 1, 1));
+    let bag = |n| CubeSet::from_iter([("red", n), ("green", n), ("blue", n)]);
+    assert!(game.possible(&bag(10)));
+    assert!(!game.possible(&bag(1)));
+    assert!(!game.possible(&CubeSet::from_iter([("red", 10), ("blue", 10)])));
 }
 
 #[test]
@@ -264,11 +414,7 @@ fn game_max_color_works() {
     let game = Game::from_str(example).unwrap();
     assert_eq!(
         game.max_colors(),
-        CubeSet {
-            red: 4,
-            green: 2,
-            blue: 6
-        }
+        CubeSet::from_iter([("red", 4), ("green", 2), ("blue", 6)])
     )
 }
 #[test]
@@ -290,3 +436,101 @@ Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
     assert_eq!(testable_exec_part2(example), 2286)
 }
+
+#[test]
+fn game_with_other_colors_works() {
+    let example = "Game 7: 2 purple, 1 red; 3 teal, 1 purple, 2 purple";
+    let game = Game::from_str(example).unwrap();
+    assert_eq!(
+        game.max_colors(),
+        CubeSet::from_iter([("purple", 3), ("red", 1), ("teal", 3)])
+    );
+    assert_eq!(game.power(&["purple", "red", "teal"]), 9);
+    assert_eq!(game.power(&["purple", "teal"]), 9);
+    assert_eq!(game.power(&["red", "green", "blue"]), 0);
+    assert!(game.possible(&CubeSet::from_str("3 purple, 1 red, 5 teal").unwrap()));
+    assert!(!game.possible(&CubeSet::from_str("12 red, 13 green, 14 blue").unwrap()));
+}
+
+#[test]
+fn parse_errors_have_spans() {
+    let error = |s: &str| {
+        let e = Game::from_str(s).unwrap_err();
+        (e.span, e.expected)
+    };
+    let e = Game::from_str("Game 3: 8 green, x blue").unwrap_err();
+    assert_eq!((e.span.clone(), e.column), (17..18, 18));
+    assert_eq!(e.to_string(), "expected number before colour at column 18");
+    assert_eq!(
+        e.on_line(4).to_string(),
+        "expected number before colour at line 4, column 18"
+    );
+    assert_eq!(error("Gaem 3: 1 red"), (0..4, Expected::Game));
+    assert_eq!(error("Game x: 1 red"), (5..5, Expected::GameId));
+    assert_eq!(error("Game 12 1 red"), (7..8, Expected::Colon));
+    assert_eq!(error("Game 12"), (7..7, Expected::Colon));
+    assert_eq!(error("Game 12é: 1 red"), (7..9, Expected::Colon));
+    let line = "Game 12é: 1 red";
+    assert!(Game::from_str(line)
+        .unwrap_err()
+        .pretty(line)
+        .ends_with("| Game 12é: 1 red\n  |        ^"));
+    assert_eq!(error("Game 1: 1 red; 2"), (16..16, Expected::Color));
+    assert_eq!(error("Game 1: 1 red; 2blue"), (16..16, Expected::Space));
+    assert_eq!(error("Game 1: 1 red 2"), (10..15, Expected::Color));
+    assert_eq!(error("Game 1: 1 red;; 2 blue").1, Expected::Number);
+    assert_eq!(
+        error("Game 1: 99999999999999999999 red"),
+        (8..28, Expected::SmallerNumber)
+    );
+    let e = CubeSet::from_str("3 blue, red").unwrap_err();
+    assert_eq!((e.span, e.expected), (8..11, Expected::Number));
+}
+
+#[test]
+fn parse_error_pretty_printing() {
+    let line = "Game 10: 8 green, x blue";
+    let e = Game::from_str(line).unwrap_err().on_line(12);
+    assert_eq!(
+        e.pretty(line),
+        "\
+error: expected number before colour
+  --> line 12, column 19
+   |
+12 | Game 10: 8 green, x blue
+   |                   ^"
+    );
+    let line = "Game 1: 3 blue, 40000000000000000000 red";
+    let e = Game::from_str(line).unwrap_err().on_line(1);
+    assert!(e.pretty(line).ends_with(
+        "1 | Game 1: 3 blue, 40000000000000000000 red\n  |                 ^^^^^^^^^^^^^^^^^^^^"
+    ));
+
+    // Columns count chars, "grün" is five bytes.
+    let line = "Game 1: 2 grün, x blau";
+    let e = Game::from_str(line).unwrap_err().on_line(1);
+    assert_eq!(e.span, 17..18);
+    assert_eq!(e.column, 17);
+    assert!(e
+        .pretty(line)
+        .ends_with("1 | Game 1: 2 grün, x blau\n  |                 ^"));
+
+    let bag = "3 red, x blue";
+    assert_eq!(
+        CubeSet::from_str(bag).unwrap_err().pretty(bag),
+        "\
+error: expected number before colour
+ --> column 8
+  |
+  | 3 red, x blue
+  |        ^"
+    );
+}
+
+#[test]
+fn game_missing_a_color_has_no_power() {
+    let game = Game::from_str("Game 1: 3 red; 2 green, 1 red").unwrap();
+    assert_eq!(game.power(&["red", "green", "blue"]), 0);
+    assert_eq!(game.power(&["red", "green"]), 6);
+    assert_eq!(testable_exec_part2("Game 1: 3 red; 2 green, 1 red"), 0);
+}
@@ -17,8 +17,38 @@ fn main() {
     for (i, input) in inputs.iter().enumerate() {
         let input_string = util::load_input(input);
         match i {
-            0 => day1::exec(&input_string),
-            1 => day2::exec(&input_string),
+            0 => {
+                day1::exec(&input_string);
+                let language = util::flag_value("--day1-language");
+                let words = util::flag_value("--day1-words");
+                let zero = util::has_flag("--day1-zero");
+                let verbose = util::has_flag("--day1-verbose");
+                let configured = language.is_some() || words.is_some() || zero;
+                if configured || verbose {
+                    let language = match language {
+                        Some(language) => language.parse().unwrap_or_else(|e| panic!("{e}")),
+                        None => day1::Language::English,
+                    };
+                    let mut vocabulary = day1::Vocabulary::new(language, zero);
+                    if let Some(path) = words {
+                        vocabulary = vocabulary
+                            .with_word_list(&util::load_input(&path))
+                            .unwrap_or_else(|e| panic!("{e}"));
+                    }
+                    if configured {
+                        day1::exec_with(&input_string, &vocabulary);
+                    }
+                    if verbose {
+                        day1::exec_report(&input_string, &vocabulary);
+                    }
+                }
+            }
+            1 => {
+                day2::exec(&input_string);
+                if let Some(bag) = util::flag_value("--day2-bag") {
+                    day2::exec_with_bag(&input_string, &bag);
+                }
+            }
             _ => panic!("Day: {} not implemented yet!", i + 1),
         }
     }
@@ -8,3 +8,44 @@ pub fn load_input(path: &str) -> String {
         .expect("Could not read file to string");
     contents
 }
+
+/// Returns true if `name` was passed on the command line.
+pub fn has_flag(name: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == name)
+}
+
+/// Returns the value following `name` on the command line, accepting both
+/// `--flag value` and `--flag=value`.
+pub fn flag_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Seeded pseudo-random numbers for tests that want the same input on every
+/// run.
+#[cfg(test)]
+pub struct Lcg(u64);
+
+#[cfg(test)]
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Next number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}